@@ -0,0 +1,204 @@
+"""Philips Hue bridges from python, built on the lightrary crate.
+
+    import asyncio
+    from lightrary import DiscoveryBroker
+
+    async def main():
+        for bridge in await DiscoveryBroker.mdns().discover():
+            bridge = await bridge.auth()
+            # press the link button on the bridge first
+            bridge, app_key = await bridge.gen_key("experiments", "laptop")
+            for room in await bridge.rooms():
+                # rooms without lights don't have a grouped light
+                if room["grouped_light"] is not None:
+                    await bridge.update_grouped_light(room["grouped_light"], on=True, brightness=60)
+            async for event in bridge.events():
+                print(event["type"], event["data"])
+
+    asyncio.run(main())
+"""
+
+import asyncio
+
+from . import _lightrary
+from ._lightrary import LightraryError
+
+__all__ = ["Bridge", "DiscoveryBroker", "EventStream", "LightraryError", "UnauthBridge"]
+
+# how long a thread waits for the next event before checking whether the stream was closed
+_EVENT_POLL_INTERVAL = 1.0
+
+
+class DiscoveryBroker:
+    """Discovers bridges with one of the protocols of `lightrary::discovery`."""
+
+    def __init__(self, inner):
+        self._inner = inner
+
+    @staticmethod
+    def mdns():
+        return DiscoveryBroker(_lightrary.DiscoveryBroker.mdns())
+
+    @staticmethod
+    def discovery_endpoint():
+        """Should be limited to 1 request every 15 minutes."""
+        return DiscoveryBroker(_lightrary.DiscoveryBroker.discovery_endpoint())
+
+    @staticmethod
+    def ssdp():
+        return DiscoveryBroker(_lightrary.DiscoveryBroker.ssdp())
+
+    @staticmethod
+    def network_scan():
+        return DiscoveryBroker(_lightrary.DiscoveryBroker.network_scan())
+
+    @staticmethod
+    def manual(addr):
+        """An ip, optionally with a port, e.g. `192.168.1.2` or `10.0.0.2:8443`."""
+        return DiscoveryBroker(_lightrary.DiscoveryBroker.manual(addr))
+
+    def verified(self):
+        """Leaves out bridges that can't be reached or aren't supported."""
+        return DiscoveryBroker(self._inner.verified())
+
+    async def discover(self):
+        bridges = await asyncio.to_thread(self._inner.discover)
+        return [UnauthBridge(bridge) for bridge in bridges]
+
+    def __repr__(self):
+        return repr(self._inner)
+
+
+class UnauthBridge:
+    """Discovered bridge which isn't authenticated yet."""
+
+    def __init__(self, inner):
+        self._inner = inner
+
+    @property
+    def id(self):
+        """Only known for some discovery protocols."""
+        return self._inner.id
+
+    @property
+    def ip(self):
+        return self._inner.ip
+
+    @property
+    def port(self):
+        return self._inner.port
+
+    async def auth(self):
+        """The bridge still needs an app key, see `Bridge.gen_key` and `Bridge.with_key`."""
+        return Bridge(await asyncio.to_thread(self._inner.auth))
+
+    def __repr__(self):
+        return repr(self._inner)
+
+
+class Bridge:
+    def __init__(self, inner):
+        self._inner = inner
+
+    @staticmethod
+    def from_descriptor(descriptor):
+        """Recreates a bridge from the json `descriptor()` returned earlier."""
+        return Bridge(_lightrary.Bridge.from_descriptor(descriptor))
+
+    def descriptor(self):
+        """Everything needed to recreate this bridge later on as json, including the app key."""
+        return self._inner.descriptor()
+
+    @property
+    def id(self):
+        return self._inner.id
+
+    @property
+    def name(self):
+        return self._inner.name
+
+    @property
+    def ip(self):
+        return self._inner.ip
+
+    @property
+    def api_version(self):
+        return self._inner.api_version
+
+    @property
+    def app_key(self):
+        return self._inner.app_key
+
+    def with_key(self, app_key):
+        return Bridge(self._inner.with_key(app_key))
+
+    async def gen_key(self, app_name, instance_name):
+        """Press the link button on the bridge first. Returns a `(Bridge, app_key)` tuple,
+        store the app key (or the descriptor) to skip this step next time."""
+        bridge, app_key = await asyncio.to_thread(self._inner.gen_key, app_name, instance_name)
+        return Bridge(bridge), app_key
+
+    async def lights(self):
+        """Every light as a dict with its `id`, `name`, `on`, `brightness` (in percent) and
+        `mirek` (its color temperature), the last two are `None` when the light lacks them."""
+        return await asyncio.to_thread(self._inner.lights)
+
+    async def rooms(self):
+        """Every room as a dict with its `id`, `name` and the id of its `grouped_light`, which
+        is `None` for a room without lights."""
+        return await asyncio.to_thread(self._inner.rooms)
+
+    async def scenes(self):
+        """Every scene as a dict with its `id`, `name` and the id of the room (or zone) it
+        belongs to as `group`."""
+        return await asyncio.to_thread(self._inner.scenes)
+
+    async def update_light(self, id, on=None, brightness=None, mirek=None):
+        """Changes what's passed and leaves the rest of the light as it is."""
+        await asyncio.to_thread(self._inner.update_light, id, on, brightness, mirek)
+
+    async def update_grouped_light(self, id, on=None, brightness=None, mirek=None):
+        """Changes every light of a room at once, pass the room's `grouped_light`."""
+        await asyncio.to_thread(self._inner.update_grouped_light, id, on, brightness, mirek)
+
+    async def recall_scene(self, id):
+        await asyncio.to_thread(self._inner.recall_scene, id)
+
+    def events(self):
+        """Async iterator over the CLIP v2 event stream, see `EventStream`."""
+        return EventStream(self._inner.events())
+
+    def __repr__(self):
+        return repr(self._inner)
+
+
+class EventStream:
+    """Events pushed by the bridge as dicts with their `id`, `creation_time`, `type` and
+    `data`. A dropped connection raises `LightraryError` and the next iteration reconnects,
+    the stream only ends after raising an error that reconnecting won't fix, e.g. a rejected
+    app key. Close it (or use it as a context manager) to stop listening."""
+
+    def __init__(self, inner):
+        self._inner = inner
+        self._closed = False
+
+    def __aiter__(self):
+        return self
+
+    async def __anext__(self):
+        # the native stream raises StopAsyncIteration itself once it ended
+        while not self._closed:
+            event = await asyncio.to_thread(self._inner.next, _EVENT_POLL_INTERVAL)
+            if event is not None:
+                return event
+        raise StopAsyncIteration
+
+    def close(self):
+        self._closed = True
+        self._inner.close()
+
+    async def __aenter__(self):
+        return self
+
+    async def __aexit__(self, *exc):
+        self.close()
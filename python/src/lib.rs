@@ -0,0 +1,423 @@
+//! Native part of the python bindings, wrapped by the `lightrary` package in
+//! `lightrary/__init__.py` which makes everything awaitable.
+//!
+//! Every call blocks on a tokio runtime with the GIL released, the package runs them in
+//! threads with `asyncio.to_thread`. Python is never entered from the runtime's own threads,
+//! which would race with the interpreter shutting down.
+
+use futures::StreamExt;
+use lightrary::discovery::{self, Discoverer, Manual};
+use lightrary::error::Error;
+use lightrary::resources::{self, BridgeDescriptor, Event, EventKind, LightUpdate, UnauthBridges};
+use pyo3::exceptions::{PyException, PyStopAsyncIteration, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::AbortHandle;
+
+pyo3::create_exception!(lightrary, LightraryError, PyException);
+
+fn to_py_err(e: Error) -> PyErr {
+    LightraryError::new_err(e.to_string())
+}
+
+fn block_on<F>(py: Python<'_>, future: F) -> F::Output
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    py.allow_threads(|| runtime().block_on(future))
+}
+
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .thread_name("lightrary")
+            .enable_all()
+            .build()
+            .expect("failed to start the runtime of the python bindings")
+    })
+}
+
+#[derive(Debug, Clone)]
+enum Protocol {
+    Mdns,
+    DiscoveryEndpoint,
+    Ssdp,
+    NetworkScan,
+    Manual(String),
+}
+
+#[pyclass(frozen, module = "lightrary._lightrary")]
+#[derive(Debug, Clone)]
+struct DiscoveryBroker {
+    protocol: Protocol,
+    verified: bool,
+}
+
+#[pymethods]
+impl DiscoveryBroker {
+    #[staticmethod]
+    fn mdns() -> Self {
+        Self::new(Protocol::Mdns)
+    }
+
+    #[staticmethod]
+    fn discovery_endpoint() -> Self {
+        Self::new(Protocol::DiscoveryEndpoint)
+    }
+
+    #[staticmethod]
+    fn ssdp() -> Self {
+        Self::new(Protocol::Ssdp)
+    }
+
+    #[staticmethod]
+    fn network_scan() -> Self {
+        Self::new(Protocol::NetworkScan)
+    }
+
+    #[staticmethod]
+    fn manual(addr: &str) -> PyResult<Self> {
+        addr.parse::<discovery::DiscoveryBroker<Manual>>()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(Self::new(Protocol::Manual(addr.to_owned())))
+    }
+
+    fn verified(&self) -> Self {
+        Self {
+            verified: true,
+            ..self.clone()
+        }
+    }
+
+    fn discover(&self, py: Python<'_>) -> PyResult<Vec<UnauthBridge>> {
+        let bridges = block_on(py, self.run()).map_err(to_py_err)?;
+        Ok(bridges.into_iter().map(UnauthBridge).collect())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "DiscoveryBroker({:?}, verified={})",
+            self.protocol, self.verified
+        )
+    }
+}
+
+impl DiscoveryBroker {
+    fn new(protocol: Protocol) -> Self {
+        Self {
+            protocol,
+            verified: false,
+        }
+    }
+
+    async fn run(&self) -> Result<UnauthBridges, Error> {
+        use discovery::DiscoveryBroker as Broker;
+
+        match &self.protocol {
+            Protocol::Mdns => discover(Broker::mdns(), self.verified).await,
+            Protocol::DiscoveryEndpoint => {
+                discover(Broker::discovery_endpoint(), self.verified).await
+            }
+            Protocol::Ssdp => discover(Broker::ssdp(), self.verified).await,
+            Protocol::NetworkScan => discover(Broker::network_scan(), self.verified).await,
+            Protocol::Manual(addr) => {
+                discover(addr.parse::<Broker<Manual>>()?, self.verified).await
+            }
+        }
+    }
+}
+
+async fn discover<D>(
+    broker: discovery::DiscoveryBroker<D>,
+    verified: bool,
+) -> Result<UnauthBridges, Error>
+where
+    D: Discoverer<Device = UnauthBridges> + Send + Sync,
+{
+    match verified {
+        true => broker.verified().discover().await,
+        false => broker.discover().await,
+    }
+}
+
+#[pyclass(frozen, module = "lightrary._lightrary")]
+struct UnauthBridge(resources::UnauthBridge);
+
+#[pymethods]
+impl UnauthBridge {
+    #[getter]
+    fn id(&self) -> Option<&str> {
+        self.0.id()
+    }
+
+    #[getter]
+    fn ip(&self) -> String {
+        self.0.ip().to_string()
+    }
+
+    #[getter]
+    fn port(&self) -> u16 {
+        self.0.port()
+    }
+
+    fn auth(&self, py: Python<'_>) -> PyResult<Bridge> {
+        match block_on(py, self.0.clone().auth()) {
+            Ok(bridge) => Ok(Bridge(Arc::new(bridge))),
+            Err((_, e)) => Err(to_py_err(e)),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "UnauthBridge(id={:?}, ip={}, port={})",
+            self.0.id(),
+            self.0.ip(),
+            self.0.port()
+        )
+    }
+}
+
+#[pyclass(frozen, module = "lightrary._lightrary")]
+struct Bridge(Arc<resources::Bridge>);
+
+#[pymethods]
+impl Bridge {
+    #[staticmethod]
+    fn from_descriptor(descriptor: &str) -> PyResult<Self> {
+        let descriptor = serde_json::from_str::<BridgeDescriptor>(descriptor)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let bridge = descriptor.into_bridge().map_err(to_py_err)?;
+        Ok(Self(Arc::new(bridge)))
+    }
+
+    fn descriptor(&self) -> PyResult<String> {
+        serde_json::to_string(&self.0.descriptor()).map_err(|e| to_py_err(e.into()))
+    }
+
+    #[getter]
+    fn id(&self) -> String {
+        self.0.config().bridge_id().to_lowercase()
+    }
+
+    #[getter]
+    fn name(&self) -> &str {
+        self.0.config().name()
+    }
+
+    #[getter]
+    fn ip(&self) -> String {
+        self.0.ip().to_string()
+    }
+
+    #[getter]
+    fn api_version(&self) -> &str {
+        self.0.config().api_version()
+    }
+
+    #[getter]
+    fn app_key(&self) -> Option<&str> {
+        self.0.app_key()
+    }
+
+    fn with_key(&self, app_key: String) -> Self {
+        Self(Arc::new(self.owned().with_key(app_key)))
+    }
+
+    fn gen_key(
+        &self,
+        py: Python<'_>,
+        app_name: &str,
+        instance_name: &str,
+    ) -> PyResult<(Bridge, String)> {
+        let (bridge, app_key) =
+            block_on(py, self.owned().gen_key(app_name, instance_name)).map_err(to_py_err)?;
+        Ok((Bridge(Arc::new(bridge)), app_key))
+    }
+
+    fn lights(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let lights = block_on(py, self.0.lights()).map_err(to_py_err)?;
+        lights
+            .into_iter()
+            .map(|light| {
+                let dict = PyDict::new(py);
+                dict.set_item("id", light.id())?;
+                dict.set_item("name", light.name())?;
+                dict.set_item("on", light.on())?;
+                dict.set_item("brightness", light.brightness())?;
+                dict.set_item("mirek", light.mirek())?;
+                Ok(dict.into_any().unbind())
+            })
+            .collect()
+    }
+
+    fn rooms(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let rooms = block_on(py, self.0.rooms()).map_err(to_py_err)?;
+        rooms
+            .into_iter()
+            .map(|room| {
+                let dict = PyDict::new(py);
+                dict.set_item("id", room.id())?;
+                dict.set_item("name", room.name())?;
+                dict.set_item("grouped_light", room.grouped_light())?;
+                Ok(dict.into_any().unbind())
+            })
+            .collect()
+    }
+
+    fn scenes(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let scenes = block_on(py, self.0.scenes()).map_err(to_py_err)?;
+        scenes
+            .into_iter()
+            .map(|scene| {
+                let dict = PyDict::new(py);
+                dict.set_item("id", scene.id())?;
+                dict.set_item("name", scene.name())?;
+                dict.set_item("group", &scene.group().rid)?;
+                Ok(dict.into_any().unbind())
+            })
+            .collect()
+    }
+
+    #[pyo3(signature = (id, on=None, brightness=None, mirek=None))]
+    fn update_light(
+        &self,
+        py: Python<'_>,
+        id: &str,
+        on: Option<bool>,
+        brightness: Option<f64>,
+        mirek: Option<u16>,
+    ) -> PyResult<()> {
+        let update = light_update(on, brightness, mirek);
+        block_on(py, self.0.update_light(id, &update)).map_err(to_py_err)
+    }
+
+    #[pyo3(signature = (id, on=None, brightness=None, mirek=None))]
+    fn update_grouped_light(
+        &self,
+        py: Python<'_>,
+        id: &str,
+        on: Option<bool>,
+        brightness: Option<f64>,
+        mirek: Option<u16>,
+    ) -> PyResult<()> {
+        let update = light_update(on, brightness, mirek);
+        block_on(py, self.0.update_grouped_light(id, &update)).map_err(to_py_err)
+    }
+
+    fn recall_scene(&self, py: Python<'_>, id: &str) -> PyResult<()> {
+        block_on(py, self.0.recall_scene(id)).map_err(to_py_err)
+    }
+
+    fn events(&self) -> EventStream {
+        let (tx, rx) = mpsc::channel(16);
+        let bridge = self.0.clone();
+        let task = runtime().spawn(async move {
+            let mut events = Box::pin(bridge.events());
+            while let Some(event) = events.next().await {
+                if tx.send(event).await.is_err() {
+                    break;
+                }
+            }
+        });
+        EventStream {
+            events: Mutex::new(rx),
+            task: task.abort_handle(),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Bridge(id={}, ip={})", self.id(), self.ip())
+    }
+}
+
+impl Bridge {
+    // the builder style methods of the crate take the bridge by value, python holds on to
+    // shared references so these start from a clone, which keeps the watchdog, the remote
+    // and the address it shares with the original
+    fn owned(&self) -> resources::Bridge {
+        resources::Bridge::clone(&self.0)
+    }
+}
+
+fn light_update(on: Option<bool>, brightness: Option<f64>, mirek: Option<u16>) -> LightUpdate {
+    let mut update = LightUpdate::new();
+    if let Some(on) = on {
+        update = update.with_on(on);
+    }
+    if let Some(brightness) = brightness {
+        update = update.with_brightness(brightness);
+    }
+    if let Some(mirek) = mirek {
+        update = update.with_mirek(mirek);
+    }
+    update
+}
+
+#[pyclass(frozen, module = "lightrary._lightrary")]
+struct EventStream {
+    events: Mutex<mpsc::Receiver<Result<Event, Error>>>,
+    task: AbortHandle,
+}
+
+#[pymethods]
+impl EventStream {
+    /// Waits up to `timeout` seconds for the next event, `None` when there wasn't any.
+    /// Raises `StopAsyncIteration` once the stream ended.
+    fn next(&self, py: Python<'_>, timeout: f64) -> PyResult<Option<PyObject>> {
+        let timeout = Duration::from_secs_f64(timeout);
+        let next = block_on(py, async {
+            tokio::time::timeout(timeout, async { self.events.lock().await.recv().await }).await
+        });
+        match next {
+            Ok(Some(Ok(event))) => event_dict(py, event).map(Some),
+            Ok(Some(Err(e))) => Err(to_py_err(e)),
+            // the task stops after an error reconnecting won't fix, or when it's closed
+            Ok(None) => Err(PyStopAsyncIteration::new_err(())),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn close(&self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn event_dict(py: Python<'_>, event: Event) -> PyResult<PyObject> {
+    let kind = match event.kind {
+        EventKind::Add => "add",
+        EventKind::Update => "update",
+        EventKind::Delete => "delete",
+        EventKind::Error => "error",
+    };
+    let data = serde_json::to_string(&event.data).map_err(|e| to_py_err(e.into()))?;
+
+    let dict = PyDict::new(py);
+    dict.set_item("id", event.id)?;
+    dict.set_item("creation_time", event.creation_time)?;
+    dict.set_item("type", kind)?;
+    dict.set_item("data", py.import("json")?.call_method1("loads", (data,))?)?;
+    Ok(dict.into_any().unbind())
+}
+
+#[pymodule]
+fn _lightrary(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<DiscoveryBroker>()?;
+    m.add_class::<UnauthBridge>()?;
+    m.add_class::<Bridge>()?;
+    m.add_class::<EventStream>()?;
+    m.add("LightraryError", m.py().get_type::<LightraryError>())?;
+    Ok(())
+}
@@ -0,0 +1,157 @@
+"""A stand-in bridge served over https on localhost, with the canned certificate next to
+this file (bridges have self-signed ones too)."""
+
+import json
+import ssl
+import threading
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+from pathlib import Path
+
+import pytest
+
+HERE = Path(__file__).parent
+
+CONFIG = {
+    "name": "Test bridge",
+    "datastoreversion": "131",
+    "swversion": "1953188020",
+    "apiversion": "1.56.0",
+    "mac": "00:17:88:6c:2a:3b",
+    "bridgeid": "001788FFFE6C2A3B",
+    "factorynew": False,
+    "modelid": "BSB002",
+    "starterkitid": "",
+}
+
+RESOURCES = {
+    "light": [
+        {
+            "id": "light-1",
+            "type": "light",
+            "owner": {"rid": "device-1", "rtype": "device"},
+            "metadata": {"name": "Desk"},
+            "on": {"on": True},
+            "dimming": {"brightness": 80.0},
+            "color_temperature": {"mirek": 366},
+        },
+        {
+            "id": "light-2",
+            "type": "light",
+            "owner": {"rid": "device-2", "rtype": "device"},
+            "metadata": {"name": "Plug"},
+            "on": {"on": False},
+        },
+    ],
+    "room": [
+        {
+            "id": "room-1",
+            "type": "room",
+            "metadata": {"name": "Office", "archetype": "office"},
+            "children": [{"rid": "device-1", "rtype": "device"}],
+            "services": [{"rid": "grouped-1", "rtype": "grouped_light"}],
+        }
+    ],
+    "scene": [
+        {
+            "id": "scene-1",
+            "type": "scene",
+            "metadata": {"name": "Concentrate"},
+            "group": {"rid": "room-1", "rtype": "room"},
+            "actions": [],
+        }
+    ],
+}
+
+EVENT = {
+    "id": "event-1",
+    "creationtime": "2024-01-01T12:00:00Z",
+    "type": "update",
+    "data": [{"id": "light-1", "type": "light", "on": {"on": False}}],
+}
+
+
+class FakeBridge(ThreadingHTTPServer):
+    daemon_threads = True
+
+    def __init__(self):
+        super().__init__(("127.0.0.1", 0), Handler)
+        context = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
+        context.load_cert_chain(HERE / "cert.pem", HERE / "key.pem")
+        self.socket = context.wrap_socket(self.socket, server_side=True)
+        # (method, path, app key header, json body) of every request
+        self.requests = []
+        # the event stream is served once, reconnects are rejected
+        self.event_stream_served = False
+
+    @property
+    def port(self):
+        return self.server_address[1]
+
+    def descriptor(self, app_key="app-key"):
+        return json.dumps(
+            {
+                "id": "001788fffe6c2a3b",
+                "ip": "127.0.0.1",
+                "port": self.port,
+                "app_key": app_key,
+                "client_key": None,
+                "config": CONFIG,
+            }
+        )
+
+
+class Handler(BaseHTTPRequestHandler):
+    def log_message(self, *args):
+        pass
+
+    def do_GET(self):
+        self.record(None)
+        if self.path == "/api/0/config":
+            return self.reply(200, CONFIG)
+        if self.path == "/eventstream/clip/v2":
+            return self.event_stream()
+        rtype = self.path.removeprefix("/clip/v2/resource/")
+        if rtype in RESOURCES:
+            return self.reply(200, {"errors": [], "data": RESOURCES[rtype]})
+        self.reply(404, {"errors": [{"description": "not found"}], "data": []})
+
+    def do_PUT(self):
+        length = int(self.headers.get("Content-Length", 0))
+        self.record(json.loads(self.rfile.read(length)))
+        rtype, rid = self.path.removeprefix("/clip/v2/resource/").split("/")
+        self.reply(200, {"errors": [], "data": [{"rid": rid, "rtype": rtype}]})
+
+    def record(self, body):
+        key = self.headers.get("hue-application-key")
+        self.server.requests.append((self.command, self.path, key, body))
+
+    def reply(self, status, body):
+        body = json.dumps(body).encode()
+        self.send_response(status)
+        self.send_header("Content-Type", "application/json")
+        self.send_header("Content-Length", str(len(body)))
+        self.end_headers()
+        self.wfile.write(body)
+
+    def event_stream(self):
+        if self.server.event_stream_served:
+            return self.reply(403, [])
+        self.server.event_stream_served = True
+        self.send_response(200)
+        self.send_header("Content-Type", "text/event-stream")
+        self.send_header("Connection", "close")
+        self.end_headers()
+        self.wfile.write(b": hi\n\n")
+        self.wfile.write(b"id: 1\ndata: " + json.dumps([EVENT]).encode() + b"\n\n")
+        self.wfile.flush()
+        self.close_connection = True
+
+
+@pytest.fixture
+def bridge():
+    server = FakeBridge()
+    thread = threading.Thread(target=server.serve_forever, daemon=True)
+    thread.start()
+    yield server
+    server.shutdown()
+    server.server_close()
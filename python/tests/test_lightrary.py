@@ -0,0 +1,109 @@
+import asyncio
+import json
+
+import pytest
+
+from lightrary import Bridge, DiscoveryBroker, LightraryError
+
+
+def run(coro):
+    return asyncio.run(coro)
+
+
+def test_manual_rejects_invalid_address():
+    with pytest.raises(ValueError):
+        DiscoveryBroker.manual("not an address")
+
+
+def test_discover_and_auth(bridge):
+    async def discover():
+        bridges = await DiscoveryBroker.manual(f"127.0.0.1:{bridge.port}").discover()
+        assert len(bridges) == 1
+        return await bridges[0].auth()
+
+    authed = run(discover())
+    assert authed.id == "001788fffe6c2a3b"
+    assert authed.name == "Test bridge"
+    assert authed.ip == "127.0.0.1"
+    assert authed.app_key is None
+
+
+def test_descriptor_round_trip(bridge):
+    restored = Bridge.from_descriptor(bridge.descriptor())
+    assert restored.app_key == "app-key"
+    assert json.loads(restored.descriptor())["port"] == bridge.port
+
+    keyed = restored.with_key("other-key")
+    assert keyed.app_key == "other-key"
+    assert restored.app_key == "app-key"
+
+
+def test_remote_descriptor_needs_the_remote(bridge):
+    descriptor = json.loads(bridge.descriptor())
+    descriptor["remote"] = True
+    with pytest.raises(LightraryError):
+        Bridge.from_descriptor(json.dumps(descriptor))
+
+
+def test_lights_rooms_and_scenes(bridge):
+    restored = Bridge.from_descriptor(bridge.descriptor())
+    lights = run(restored.lights())
+    rooms = run(restored.rooms())
+    scenes = run(restored.scenes())
+
+    assert lights == [
+        {"id": "light-1", "name": "Desk", "on": True, "brightness": 80.0, "mirek": 366},
+        {"id": "light-2", "name": "Plug", "on": False, "brightness": None, "mirek": None},
+    ]
+    assert rooms == [{"id": "room-1", "name": "Office", "grouped_light": "grouped-1"}]
+    assert scenes == [{"id": "scene-1", "name": "Concentrate", "group": "room-1"}]
+    assert all(key == "app-key" for _, _, key, _ in bridge.requests)
+
+
+def test_updates_only_send_what_changed(bridge):
+    restored = Bridge.from_descriptor(bridge.descriptor())
+
+    async def update():
+        await restored.update_light("light-1", on=False)
+        await restored.update_grouped_light("grouped-1", brightness=25.0, mirek=300)
+        await restored.recall_scene("scene-1")
+
+    run(update())
+    assert [request[:2] + request[3:] for request in bridge.requests] == [
+        ("PUT", "/clip/v2/resource/light/light-1", {"on": {"on": False}}),
+        (
+            "PUT",
+            "/clip/v2/resource/grouped_light/grouped-1",
+            {"dimming": {"brightness": 25.0}, "color_temperature": {"mirek": 300}},
+        ),
+        ("PUT", "/clip/v2/resource/scene/scene-1", {"recall": {"action": "active"}}),
+    ]
+
+
+def test_without_key_raises(bridge):
+    restored = Bridge.from_descriptor(bridge.descriptor(app_key=None))
+    with pytest.raises(LightraryError):
+        run(restored.lights())
+    assert bridge.requests == []
+
+
+def test_event_stream_ends_after_permanent_error(bridge):
+    restored = Bridge.from_descriptor(bridge.descriptor())
+
+    async def listen():
+        events, errors = [], []
+        async with restored.events() as stream:
+            while True:
+                try:
+                    events.append(await stream.__anext__())
+                except LightraryError as e:
+                    errors.append(e)
+                except StopAsyncIteration:
+                    return events, errors
+
+    events, errors = run(asyncio.wait_for(listen(), timeout=30))
+    assert [event["id"] for event in events] == ["event-1"]
+    assert events[0]["type"] == "update"
+    assert events[0]["data"] == [{"id": "light-1", "type": "light", "on": {"on": False}}]
+    # the reconnect is rejected, which ends the stream
+    assert len(errors) == 1
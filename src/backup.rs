@@ -0,0 +1,948 @@
+//! Export the user configuration of a bridge and restore it onto the same or a replacement bridge.
+//!
+//! A [`Backup`] contains everything a user configures on their bridge: device names, rooms,
+//! zones, scenes, behavior instances, CLIP sensors, sensor configs, v1 schedules, rules and
+//! resource links. It is plain serde data so it can be stored in any format you like, only
+//! the fields that get restored are kept and it contains no app keys.
+//!
+//! Restoring onto a replacement bridge means every resource ends up with a new id, devices
+//! are recognized by their zigbee MAC address (falling back to their name) and every id in
+//! the backup is mapped to the id of its counterpart on the new bridge.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use lightrary::backup::Backup;
+//! use lightrary::resources::Bridge;
+//!
+//! # async fn run(old: Bridge, new: Bridge) -> Result<(), Box<dyn std::error::Error>> {
+//! let backup = old.backup().await?;
+//! let json = serde_json::to_string(&backup)?;
+//!
+//! // ... the bridge is replaced and all devices are paired to the new one
+//!
+//! let backup = serde_json::from_str::<Backup>(&json)?;
+//! let report = new.restore(&backup).await?;
+//! for failed in report.failed {
+//!     eprintln!("could not restore {}: {}", failed.resource, failed.err);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::resources::{relative_address, Bridge, ResourceIdentifier};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+
+/// Version of the backup format, bumped whenever a backup can't be read by an older version.
+pub const BACKUP_VERSION: u32 = 1;
+
+// fields of each resource which are user configuration, everything else is either read-only
+// or managed by the bridge
+const ROOM_FIELDS: &[&str] = &["children", "metadata"];
+const SCENE_FIELDS: &[&str] = &[
+    "actions",
+    "metadata",
+    "group",
+    "palette",
+    "speed",
+    "auto_dynamic",
+];
+const BEHAVIOR_INSTANCE_FIELDS: &[&str] = &["script_id", "enabled", "configuration", "metadata"];
+const CLIP_SENSOR_FIELDS: &[&str] = &[
+    "name",
+    "type",
+    "modelid",
+    "manufacturername",
+    "swversion",
+    "uniqueid",
+    "recycle",
+];
+const SENSOR_CONFIG_FIELDS: &[&str] = &[
+    "on",
+    "sensitivity",
+    "ledindication",
+    "tholddark",
+    "tholdoffset",
+];
+const CLIP_SENSOR_STATE_FIELDS: &[&str] = &["status", "flag", "presence"];
+const SCHEDULE_FIELDS: &[&str] = &[
+    "name",
+    "description",
+    "command",
+    "localtime",
+    "status",
+    "autodelete",
+    "recycle",
+];
+const RULE_FIELDS: &[&str] = &["name", "status", "recycle", "conditions", "actions"];
+const RESOURCE_LINK_FIELDS: &[&str] =
+    &["name", "description", "type", "classid", "recycle", "links"];
+
+/// Snapshot of the user configuration of a bridge.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backup {
+    pub version: u32,
+    /// Id of the bridge this backup was taken from.
+    pub bridge_id: String,
+    pub devices: Vec<DeviceRecord>,
+    /// Every CLIP v2 resource, used to map ids between bridges.
+    pub resources: Vec<ResourceRecord>,
+    pub rooms: Vec<Value>,
+    pub zones: Vec<Value>,
+    pub scenes: Vec<Value>,
+    pub behavior_instances: Vec<Value>,
+    pub sensors: Vec<V1Record>,
+    pub schedules: Vec<V1Record>,
+    pub rules: Vec<V1Record>,
+    pub resource_links: Vec<V1Record>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceRecord {
+    pub id: String,
+    pub metadata: Value,
+    pub mac_address: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceRecord {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub rtype: String,
+    pub id_v1: Option<String>,
+    pub owner: Option<ResourceIdentifier>,
+}
+
+/// v1 resource along with its id. Schedules, rules and resource links only keep the fields
+/// that get restored, sensors are kept as the bridge returned them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct V1Record {
+    pub id: String,
+    pub data: Value,
+}
+
+impl Backup {
+    fn name_of(value: &Value) -> Option<&str> {
+        value.get("metadata")?.get("name")?.as_str()
+    }
+}
+
+/// Outcome of [`Bridge::restore`], restoring carries on when a single resource fails.
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    /// CLIP v2 ids in the backup mapped to the ids on the restored bridge.
+    pub ids: HashMap<String, String>,
+    /// v1 addresses in the backup (e.g. `/sensors/5`) mapped to those on the restored bridge.
+    pub v1_ids: HashMap<String, String>,
+    pub failed: Vec<RestoreFailed>,
+}
+
+#[derive(Debug)]
+pub struct RestoreFailed {
+    /// Address of the resource in the backup, e.g. `/room/<id>` or `/rules/3`.
+    pub resource: String,
+    pub err: Error,
+}
+
+#[derive(Deserialize)]
+struct DeviceZigbee {
+    owner: ResourceIdentifier,
+    mac_address: String,
+}
+
+impl Bridge {
+    /// Takes a snapshot of the user configuration of this bridge.
+    pub async fn backup(&self) -> Result<Backup, Error> {
+        let (resources, devices, zigbee) = futures::try_join!(
+            self.v2_get_all::<Value>(),
+            self.v2_get::<Value>("device"),
+            self.v2_get::<DeviceZigbee>("zigbee_connectivity"),
+        )?;
+        let (sensors, schedules, rules, resource_links) = futures::try_join!(
+            self.v1_list::<Value>("/sensors"),
+            self.v1_list::<Value>("/schedules"),
+            self.v1_list::<Value>("/rules"),
+            self.v1_list::<Value>("/resourcelinks"),
+        )?;
+
+        let macs = zigbee
+            .into_iter()
+            .map(|conn| (conn.owner.rid, conn.mac_address))
+            .collect::<HashMap<_, _>>();
+        let devices = devices
+            .into_iter()
+            .filter_map(|device| {
+                let id = device.get("id")?.as_str()?.to_owned();
+                Some(DeviceRecord {
+                    mac_address: macs.get(&id).cloned(),
+                    metadata: device.get("metadata").cloned().unwrap_or_default(),
+                    id,
+                })
+            })
+            .collect();
+
+        let of_type = |rtype: &str| {
+            resources
+                .iter()
+                .filter(|r| r.get("type").and_then(Value::as_str) == Some(rtype))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let records = |list: Vec<(String, Value)>, fields: Option<&[&str]>| {
+            list.into_iter()
+                .map(|(id, data)| {
+                    let mut data = match fields {
+                        Some(fields) => pick(&data, fields),
+                        None => data,
+                    };
+                    strip_app_key(&mut data);
+                    V1Record { id, data }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        Ok(Backup {
+            version: BACKUP_VERSION,
+            bridge_id: self.config.bridgeid.clone(),
+            devices,
+            rooms: of_type("room"),
+            zones: of_type("zone"),
+            scenes: of_type("scene"),
+            behavior_instances: of_type("behavior_instance"),
+            resources: resources
+                .iter()
+                .filter_map(|r| ResourceRecord::deserialize(r).ok())
+                .collect(),
+            sensors: records(sensors, None),
+            schedules: records(schedules, Some(SCHEDULE_FIELDS)),
+            rules: records(rules, Some(RULE_FIELDS)),
+            resource_links: records(resource_links, Some(RESOURCE_LINK_FIELDS)),
+        })
+    }
+
+    /// Re-applies a backup onto this bridge, which is either the bridge the backup was taken
+    /// from or its replacement. Resources that already exist (by name) are updated instead of
+    /// duplicated so restoring is safe to repeat.
+    pub async fn restore(&self, backup: &Backup) -> Result<RestoreReport, Error> {
+        if backup.version > BACKUP_VERSION {
+            return Err(Error::UnsupportedBackupVersion(backup.version));
+        }
+
+        let current = self.backup().await?;
+        let mut restore = Restore {
+            bridge: self,
+            ids: IdMap::default(),
+            report: RestoreReport::default(),
+        };
+
+        restore.ids.map_devices(backup, &current);
+        restore.ids.map_owned(&backup.resources, &current.resources);
+        restore.device_names(backup).await;
+        restore.by_name("room", &backup.rooms, &current.rooms).await;
+        restore.by_name("zone", &backup.zones, &current.zones).await;
+        restore.scenes(&backup.scenes, &current.scenes).await;
+        restore
+            .by_name(
+                "behavior_instance",
+                &backup.behavior_instances,
+                &current.behavior_instances,
+            )
+            .await;
+
+        // new rooms and zones come with their own grouped lights and v1 ids, fetch them so
+        // those can be mapped too
+        let refreshed = self.v2_get_all::<Value>().await?;
+        let refreshed = refreshed
+            .iter()
+            .filter_map(|r| ResourceRecord::deserialize(r).ok())
+            .collect::<Vec<_>>();
+        restore.ids.map_owned(&backup.resources, &refreshed);
+        restore.ids.map_v1(&backup.resources, &refreshed);
+
+        restore.sensors(&backup.sensors, &current.sensors).await;
+        restore
+            .v1_resources(
+                "/schedules",
+                SCHEDULE_FIELDS,
+                &backup.schedules,
+                &current.schedules,
+            )
+            .await;
+        restore
+            .v1_resources("/rules", RULE_FIELDS, &backup.rules, &current.rules)
+            .await;
+        restore
+            .v1_resources(
+                "/resourcelinks",
+                RESOURCE_LINK_FIELDS,
+                &backup.resource_links,
+                &current.resource_links,
+            )
+            .await;
+
+        let mut report = restore.report;
+        report.ids = restore.ids.v2;
+        report.v1_ids = restore.ids.v1;
+        Ok(report)
+    }
+}
+
+#[derive(Default)]
+struct IdMap {
+    v2: HashMap<String, String>,
+    v1: HashMap<String, String>,
+}
+
+impl IdMap {
+    fn remap_v2(&self, value: &mut Value) {
+        match value {
+            Value::String(s) => {
+                if let Some(new) = self.v2.get(s.as_str()) {
+                    *s = new.clone();
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|v| self.remap_v2(v)),
+            Value::Object(map) => map.values_mut().for_each(|v| self.remap_v2(v)),
+            _ => {}
+        }
+    }
+
+    // v1 addresses point into a resource, e.g. `/sensors/5/state/buttonevent`, so the
+    // resource part is matched as a prefix
+    fn remap_v1(&self, value: &mut Value) {
+        match value {
+            Value::String(s) => {
+                let mapped = self.v1.iter().find_map(|(old, new)| {
+                    let rest = s.strip_prefix(old.as_str())?;
+                    (rest.is_empty() || rest.starts_with('/')).then(|| format!("{}{}", new, rest))
+                });
+                if let Some(mapped) = mapped {
+                    *s = mapped;
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|v| self.remap_v1(v)),
+            Value::Object(map) => map.values_mut().for_each(|v| self.remap_v1(v)),
+            _ => {}
+        }
+    }
+
+    // schedules address their command including the app key it runs with, e.g.
+    // `/api/<app key>/lights/3/state`, which has to become the key of the restored bridge
+    fn remap_command(&self, body: &mut Value, app_key: &str) {
+        if let Some(Value::String(address)) = body.pointer_mut("/command/address") {
+            let mut relative = Value::from(relative_address(address));
+            self.remap_v1(&mut relative);
+            if let Value::String(relative) = relative {
+                *address = format!("/api/{}{}", app_key, relative);
+            }
+        }
+    }
+
+    fn map_devices(&mut self, backup: &Backup, current: &Backup) {
+        let mut taken = HashSet::new();
+        for old in &backup.devices {
+            let by_mac = old.mac_address.as_ref().and_then(|mac| {
+                current
+                    .devices
+                    .iter()
+                    .find(|new| new.mac_address.as_ref() == Some(mac))
+            });
+            let by_name = || {
+                let name = old.metadata.get("name")?;
+                let mut named = current
+                    .devices
+                    .iter()
+                    .filter(|new| new.metadata.get("name") == Some(name));
+                // only trust a name when it is unique
+                match (named.next(), named.next()) {
+                    (Some(new), None) => Some(new),
+                    _ => None,
+                }
+            };
+
+            if let Some(new) = by_mac.or_else(by_name) {
+                if taken.insert(new.id.clone()) {
+                    self.v2.insert(old.id.clone(), new.id.clone());
+                }
+            }
+        }
+
+        // the bridge and its home are singletons, they map regardless of anything else
+        for rtype in ["bridge", "bridge_home"] {
+            let old = backup.resources.iter().find(|r| r.rtype == rtype);
+            let new = current.resources.iter().find(|r| r.rtype == rtype);
+            if let (Some(old), Some(new)) = (old, new) {
+                self.v2.insert(old.id.clone(), new.id.clone());
+                if let (Some(old_owner), Some(new_owner)) = (&old.owner, &new.owner) {
+                    self.v2.insert(old_owner.rid.clone(), new_owner.rid.clone());
+                }
+            }
+        }
+    }
+
+    // services (lights, buttons, grouped lights, ...) are mapped through their owner, when a
+    // device has multiple services of the same type they're matched in order
+    fn map_owned(&mut self, old: &[ResourceRecord], new: &[ResourceRecord]) {
+        let mut owned = HashMap::<(&str, &str), Vec<&str>>::new();
+        for resource in new {
+            if let Some(owner) = &resource.owner {
+                owned
+                    .entry((owner.rid.as_str(), resource.rtype.as_str()))
+                    .or_default()
+                    .push(&resource.id);
+            }
+        }
+
+        let mut index = HashMap::<(String, &str), usize>::new();
+        for resource in old {
+            let owner = match &resource.owner {
+                Some(owner) => owner,
+                None => continue,
+            };
+            let new_owner = match self.v2.get(&owner.rid) {
+                Some(new_owner) => new_owner.clone(),
+                None => continue,
+            };
+            let i = index
+                .entry((new_owner.clone(), resource.rtype.as_str()))
+                .or_default();
+            if let Some(new_id) = owned
+                .get(&(new_owner.as_str(), resource.rtype.as_str()))
+                .and_then(|ids| ids.get(*i))
+            {
+                self.v2.insert(resource.id.clone(), (*new_id).to_owned());
+            }
+            *i += 1;
+        }
+    }
+
+    fn map_v1(&mut self, old: &[ResourceRecord], new: &[ResourceRecord]) {
+        let new = new
+            .iter()
+            .map(|r| (r.id.as_str(), r))
+            .collect::<HashMap<_, _>>();
+        for resource in old {
+            let old_v1 = match &resource.id_v1 {
+                Some(id_v1) if !id_v1.is_empty() => id_v1,
+                _ => continue,
+            };
+            let new_v1 = self
+                .v2
+                .get(&resource.id)
+                .and_then(|id| new.get(id.as_str()))
+                .and_then(|r| r.id_v1.as_ref());
+            if let Some(new_v1) = new_v1 {
+                self.v1.insert(old_v1.clone(), new_v1.clone());
+            }
+        }
+    }
+
+    // children and scene actions pointing at devices that were never paired to this bridge
+    // would make the whole request fail, leave them out instead
+    fn drop_unmapped(&self, body: &mut Value) {
+        let mapped = self.v2.values().map(String::as_str).collect::<HashSet<_>>();
+        let is_mapped = |v: &Value| {
+            let rid = v
+                .get("rid")
+                .or_else(|| v.get("target").and_then(|t| t.get("rid")))
+                .and_then(Value::as_str);
+            rid.is_none_or(|rid| mapped.contains(rid))
+        };
+        for key in ["children", "actions"] {
+            if let Some(Value::Array(list)) = body.get_mut(key) {
+                list.retain(is_mapped);
+            }
+        }
+    }
+}
+
+struct Restore<'a> {
+    bridge: &'a Bridge,
+    ids: IdMap,
+    report: RestoreReport,
+}
+
+impl Restore<'_> {
+    fn failed(&mut self, resource: String, err: Error) {
+        self.report.failed.push(RestoreFailed { resource, err });
+    }
+
+    async fn device_names(&mut self, backup: &Backup) {
+        for device in &backup.devices {
+            let new = match self.ids.v2.get(&device.id) {
+                Some(new) => new.clone(),
+                None => continue,
+            };
+            let body = serde_json::json!({ "metadata": device.metadata });
+            let path = format!("/device/{}", new);
+            if let Err(e) = self.bridge.v2_send(Method::PUT, &path, Some(&body)).await {
+                self.failed(format!("/device/{}", device.id), e);
+            }
+        }
+    }
+
+    // rooms, zones and behavior instances are all matched by name
+    async fn by_name(&mut self, rtype: &str, old: &[Value], current: &[Value]) {
+        let fields = match rtype {
+            "behavior_instance" => BEHAVIOR_INSTANCE_FIELDS,
+            _ => ROOM_FIELDS,
+        };
+        for resource in old {
+            let existing = current.iter().find(|c| {
+                Backup::name_of(c).is_some() && Backup::name_of(c) == Backup::name_of(resource)
+            });
+            self.upsert(rtype, fields, resource, existing).await;
+        }
+    }
+
+    // scenes are matched by name within the same (mapped) group
+    async fn scenes(&mut self, old: &[Value], current: &[Value]) {
+        for scene in old {
+            let group = scene
+                .get("group")
+                .and_then(|g| g.get("rid"))
+                .and_then(Value::as_str)
+                .and_then(|rid| self.ids.v2.get(rid));
+            let existing = current.iter().find(|c| {
+                Backup::name_of(c) == Backup::name_of(scene)
+                    && c.get("group")
+                        .and_then(|g| g.get("rid"))
+                        .and_then(Value::as_str)
+                        == group.map(String::as_str)
+            });
+            self.upsert("scene", SCENE_FIELDS, scene, existing).await;
+        }
+    }
+
+    async fn upsert(
+        &mut self,
+        rtype: &str,
+        fields: &[&str],
+        old: &Value,
+        existing: Option<&Value>,
+    ) {
+        let old_id = old.get("id").and_then(Value::as_str).unwrap_or_default();
+        let mut body = pick(old, fields);
+        self.ids.remap_v2(&mut body);
+        self.ids.drop_unmapped(&mut body);
+
+        let res = match existing.and_then(|e| e.get("id")).and_then(Value::as_str) {
+            Some(id) => {
+                // which group a scene belongs to, or which script an instance runs, can't be
+                // changed after creation
+                if let Some(body) = body.as_object_mut() {
+                    body.remove("group");
+                    body.remove("script_id");
+                }
+                let path = format!("/{}/{}", rtype, id);
+                self.bridge
+                    .v2_send(Method::PUT, &path, Some(&body))
+                    .await
+                    .map(|_| id.to_owned())
+            }
+            None => {
+                body["type"] = Value::from(rtype);
+                let path = format!("/{}", rtype);
+                self.bridge
+                    .v2_send(Method::POST, &path, Some(&body))
+                    .await
+                    .and_then(|created| {
+                        created.into_iter().next().map(|r| r.rid).ok_or_else(|| {
+                            Error::UnexpectedResponse("missing id of created resource".into())
+                        })
+                    })
+            }
+        };
+
+        match res {
+            Ok(new_id) => {
+                self.ids.v2.insert(old_id.to_owned(), new_id);
+            }
+            Err(e) => self.failed(format!("/{}/{}", rtype, old_id), e),
+        }
+    }
+
+    async fn sensors(&mut self, old: &[V1Record], current: &[V1Record]) {
+        for sensor in old {
+            let old_address = format!("/sensors/{}", sensor.id);
+            let is_clip = sensor.data["type"]
+                .as_str()
+                .is_some_and(|t| t.starts_with("CLIP"));
+
+            let new_id = if is_clip {
+                let existing = current.iter().find(|c| {
+                    c.data.get("uniqueid").is_some()
+                        && c.data.get("uniqueid") == sensor.data.get("uniqueid")
+                });
+                match existing {
+                    Some(existing) => existing.id.clone(),
+                    None => {
+                        let body = pick(&sensor.data, CLIP_SENSOR_FIELDS);
+                        match self.bridge.v1_create("/sensors", &body).await {
+                            Ok(id) => id,
+                            Err(e) => {
+                                self.failed(old_address, e);
+                                continue;
+                            }
+                        }
+                    }
+                }
+            } else {
+                match self.ids.v1.get(&old_address) {
+                    Some(new) => new.trim_start_matches("/sensors/").to_owned(),
+                    None => continue,
+                }
+            };
+            let new_address = format!("/sensors/{}", new_id);
+            self.ids.v1.insert(old_address.clone(), new_address.clone());
+
+            let name = pick(&sensor.data, &["name"]);
+            let config = pick(&sensor.data["config"], SENSOR_CONFIG_FIELDS);
+            let state = pick(&sensor.data["state"], CLIP_SENSOR_STATE_FIELDS);
+            let mut changes = vec![
+                (new_address.clone(), name),
+                (format!("{}/config", new_address), config),
+            ];
+            if is_clip {
+                changes.push((format!("{}/state", new_address), state));
+            }
+            for (path, body) in changes {
+                if body.as_object().is_none_or(Map::is_empty) {
+                    continue;
+                }
+                if let Err(e) = self.bridge.v1_send(Method::PUT, &path, Some(&body)).await {
+                    self.failed(old_address.clone(), e);
+                }
+            }
+        }
+    }
+
+    // schedules, rules and resource links are matched by name and reference other resources
+    // by their v1 address
+    async fn v1_resources(
+        &mut self,
+        path: &str,
+        fields: &[&str],
+        old: &[V1Record],
+        current: &[V1Record],
+    ) {
+        for resource in old {
+            let old_address = format!("{}/{}", path, resource.id);
+            let mut body = pick(&resource.data, fields);
+            self.ids.remap_v1(&mut body);
+            if path == "/schedules" {
+                // restoring fails before getting here when the bridge has no app key
+                let app_key = self.bridge.app_key().unwrap_or_default();
+                self.ids.remap_command(&mut body, app_key);
+            }
+            enabled_or_disabled(&mut body);
+
+            let existing = current.iter().find(|c| {
+                c.data.get("name").is_some() && c.data.get("name") == resource.data.get("name")
+            });
+            let res = match existing {
+                Some(existing) => {
+                    let address = format!("{}/{}", path, existing.id);
+                    self.bridge
+                        .v1_send(Method::PUT, &address, Some(&body))
+                        .await
+                        .map(|_| existing.id.clone())
+                }
+                None => self.bridge.v1_create(path, &body).await,
+            };
+
+            match res {
+                Ok(id) => {
+                    self.ids.v1.insert(old_address, format!("{}/{}", path, id));
+                }
+                Err(e) => self.failed(old_address, e),
+            }
+        }
+    }
+}
+
+// schedules address their command including the app key, which stays out of a backup, the
+// key of the restored bridge is put back in by `IdMap::remap_command`
+fn strip_app_key(body: &mut Value) {
+    if let Some(Value::String(address)) = body.pointer_mut("/command/address") {
+        *address = relative_address(address).to_owned();
+    }
+}
+
+// the other statuses of schedules and rules are set by the bridge, it only accepts these two
+fn enabled_or_disabled(body: &mut Value) {
+    if let Some(status) = body.get_mut("status") {
+        if status != "enabled" {
+            *status = Value::from("disabled");
+        }
+    }
+}
+
+fn pick(value: &Value, fields: &[&str]) -> Value {
+    let picked = fields
+        .iter()
+        .filter_map(|field| Some((field.to_string(), value.get(field)?.clone())))
+        .collect::<Map<_, _>>();
+    Value::Object(picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ids(v2: &[(&str, &str)], v1: &[(&str, &str)]) -> IdMap {
+        let map = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(old, new)| (old.to_string(), new.to_string()))
+                .collect()
+        };
+        IdMap {
+            v2: map(v2),
+            v1: map(v1),
+        }
+    }
+
+    fn device(id: &str, name: &str, mac: Option<&str>) -> DeviceRecord {
+        DeviceRecord {
+            id: id.to_owned(),
+            metadata: json!({ "name": name }),
+            mac_address: mac.map(str::to_owned),
+        }
+    }
+
+    fn resource(id: &str, rtype: &str, owner: Option<&str>) -> ResourceRecord {
+        ResourceRecord {
+            id: id.to_owned(),
+            rtype: rtype.to_owned(),
+            id_v1: None,
+            owner: owner.map(|rid| ResourceIdentifier {
+                rid: rid.to_owned(),
+                rtype: "device".to_owned(),
+            }),
+        }
+    }
+
+    fn backup(devices: Vec<DeviceRecord>, resources: Vec<ResourceRecord>) -> Backup {
+        Backup {
+            version: BACKUP_VERSION,
+            bridge_id: String::new(),
+            devices,
+            resources,
+            rooms: Vec::new(),
+            zones: Vec::new(),
+            scenes: Vec::new(),
+            behavior_instances: Vec::new(),
+            sensors: Vec::new(),
+            schedules: Vec::new(),
+            rules: Vec::new(),
+            resource_links: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn remap_v2_replaces_every_mapped_id() {
+        let ids = ids(&[("old-light", "new-light"), ("old-room", "new-room")], &[]);
+        let mut body = json!({
+            "group": { "rid": "old-room", "rtype": "room" },
+            "actions": [
+                { "target": { "rid": "old-light", "rtype": "light" } },
+                { "target": { "rid": "unknown", "rtype": "light" } },
+            ],
+        });
+        ids.remap_v2(&mut body);
+        assert_eq!(body["group"]["rid"], "new-room");
+        assert_eq!(body["actions"][0]["target"]["rid"], "new-light");
+        assert_eq!(body["actions"][1]["target"]["rid"], "unknown");
+    }
+
+    #[test]
+    fn remap_v1_matches_resource_prefixes() {
+        let ids = ids(
+            &[],
+            &[("/sensors/5", "/sensors/12"), ("/lights/1", "/lights/7")],
+        );
+        let mut body = json!({
+            "conditions": [
+                { "address": "/sensors/5/state/buttonevent" },
+                { "address": "/sensors/50/state/buttonevent" },
+            ],
+            "actions": [{ "address": "/lights/1" }],
+        });
+        ids.remap_v1(&mut body);
+        assert_eq!(
+            body["conditions"][0]["address"],
+            "/sensors/12/state/buttonevent"
+        );
+        // `/sensors/5` is a prefix of `/sensors/50` but not the same resource
+        assert_eq!(
+            body["conditions"][1]["address"],
+            "/sensors/50/state/buttonevent"
+        );
+        assert_eq!(body["actions"][0]["address"], "/lights/7");
+    }
+
+    #[test]
+    fn remap_command_replaces_the_app_key() {
+        let ids = ids(&[], &[("/lights/3", "/lights/9")]);
+        let mut body = json!({ "command": { "address": "/api/oldkey/lights/3/state" } });
+        ids.remap_command(&mut body, "newkey");
+        assert_eq!(body["command"]["address"], "/api/newkey/lights/9/state");
+
+        let mut body = json!({ "command": { "address": "/api/oldkey/groups/0/action" } });
+        ids.remap_command(&mut body, "newkey");
+        assert_eq!(body["command"]["address"], "/api/newkey/groups/0/action");
+    }
+
+    #[test]
+    fn map_devices_by_mac_then_unique_name() {
+        let old = backup(
+            vec![
+                device("a", "kitchen", Some("00:17:88:01:00:00:00:01")),
+                device("b", "hallway", None),
+                device("c", "lamp", None),
+            ],
+            vec![
+                resource("old-bridge", "bridge", Some("old-bridge-device")),
+                resource("old-home", "bridge_home", None),
+            ],
+        );
+        let new = backup(
+            vec![
+                // renamed since, still the same device
+                device("x", "renamed", Some("00:17:88:01:00:00:00:01")),
+                device("y", "hallway", None),
+                device("z1", "lamp", None),
+                device("z2", "lamp", None),
+            ],
+            vec![
+                resource("new-bridge", "bridge", Some("new-bridge-device")),
+                resource("new-home", "bridge_home", None),
+            ],
+        );
+
+        let mut ids = IdMap::default();
+        ids.map_devices(&old, &new);
+        assert_eq!(ids.v2["a"], "x");
+        assert_eq!(ids.v2["b"], "y");
+        // ambiguous names aren't trusted
+        assert!(!ids.v2.contains_key("c"));
+        assert_eq!(ids.v2["old-bridge"], "new-bridge");
+        assert_eq!(ids.v2["old-bridge-device"], "new-bridge-device");
+        assert_eq!(ids.v2["old-home"], "new-home");
+    }
+
+    #[test]
+    fn map_devices_maps_every_device_once() {
+        let old = backup(
+            vec![device("a", "lamp", None), device("b", "lamp", None)],
+            Vec::new(),
+        );
+        let new = backup(vec![device("x", "lamp", None)], Vec::new());
+
+        let mut ids = IdMap::default();
+        ids.map_devices(&old, &new);
+        assert_eq!(ids.v2.len(), 1);
+    }
+
+    #[test]
+    fn map_owned_through_owner_in_order() {
+        let old = vec![
+            resource("old-light", "light", Some("a")),
+            resource("old-button-1", "button", Some("a")),
+            resource("old-button-2", "button", Some("a")),
+            resource("orphan", "light", Some("unpaired")),
+        ];
+        let new = vec![
+            resource("new-light", "light", Some("x")),
+            resource("new-button-1", "button", Some("x")),
+            resource("new-button-2", "button", Some("x")),
+        ];
+
+        let mut ids = ids(&[("a", "x")], &[]);
+        ids.map_owned(&old, &new);
+        assert_eq!(ids.v2["old-light"], "new-light");
+        assert_eq!(ids.v2["old-button-1"], "new-button-1");
+        assert_eq!(ids.v2["old-button-2"], "new-button-2");
+        assert!(!ids.v2.contains_key("orphan"));
+    }
+
+    #[test]
+    fn pick_only_keeps_fields() {
+        let value = json!({ "name": "a", "type": "CLIPGenericFlag", "state": {}, "id": "1" });
+        assert_eq!(
+            pick(&value, &["name", "type", "missing"]),
+            json!({ "name": "a", "type": "CLIPGenericFlag" })
+        );
+        assert_eq!(pick(&Value::Null, &["name"]), json!({}));
+    }
+
+    #[test]
+    fn schedule_records_without_app_keys() {
+        let schedule = json!({
+            "name": "wake up",
+            "owner": "oldkey",
+            "command": {
+                "address": "/api/oldkey/groups/0/action",
+                "method": "PUT",
+                "body": { "on": true },
+            },
+            "localtime": "W124/T07:00:00",
+            "status": "enabled",
+            "created": "2024-01-01T00:00:00",
+        });
+        let mut data = pick(&schedule, SCHEDULE_FIELDS);
+        strip_app_key(&mut data);
+        assert_eq!(data["command"]["address"], "/groups/0/action");
+        assert!(data.get("owner").is_none());
+        assert!(data.get("created").is_none());
+        assert!(!data.to_string().contains("oldkey"));
+    }
+
+    #[test]
+    fn only_enabled_or_disabled() {
+        for (status, restored) in [
+            ("enabled", "enabled"),
+            ("disabled", "disabled"),
+            ("resourcedeleted", "disabled"),
+            ("somethingnew", "disabled"),
+        ] {
+            let mut body = json!({ "name": "rule", "status": status });
+            enabled_or_disabled(&mut body);
+            assert_eq!(body["status"], restored);
+        }
+
+        // resource links don't have a status
+        let mut body = json!({ "name": "link" });
+        enabled_or_disabled(&mut body);
+        assert!(body.get("status").is_none());
+    }
+
+    #[test]
+    fn drop_unmapped_children_and_actions() {
+        let ids = ids(&[("a", "x")], &[]);
+        let mut body = json!({
+            "children": [
+                { "rid": "x", "rtype": "device" },
+                { "rid": "never-paired", "rtype": "device" },
+            ],
+            "actions": [
+                { "target": { "rid": "x", "rtype": "light" } },
+                { "target": { "rid": "never-paired", "rtype": "light" } },
+            ],
+            "metadata": { "name": "kitchen" },
+        });
+        ids.drop_unmapped(&mut body);
+        assert_eq!(body["children"], json!([{ "rid": "x", "rtype": "device" }]));
+        assert_eq!(
+            body["actions"],
+            json!([{ "target": { "rid": "x", "rtype": "light" } }])
+        );
+        assert_eq!(body["metadata"]["name"], "kitchen");
+    }
+}
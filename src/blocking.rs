@@ -0,0 +1,349 @@
+//! Synchronous api for scripts and tools which don't want to deal with async, behind the
+//! `blocking` feature.
+//!
+//! Mirrors discovery, authentication and the resource api of [`Bridge`](crate::resources::Bridge)
+//! with blocking signatures. Everything runs on a tokio runtime started in the background on
+//! first use, so don't call any of this from within an async context, it panics.
+//!
+//! ```no_run
+//! use lightrary::blocking::DiscoveryBroker;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let (bridges, _failed) = DiscoveryBroker::mdns().discover()?.auth();
+//! for bridge in bridges {
+//!     let (bridge, _app_key) = bridge.gen_key("my_script", "laptop")?;
+//!     for sensor in bridge.sensors()? {
+//!         println!("{}", sensor.name());
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::backup::{Backup, RestoreReport};
+#[cfg(feature = "cloud-discovery")]
+use crate::discovery::DiscoveryEndpoint;
+#[cfg(feature = "mdns")]
+use crate::discovery::Mdns;
+use crate::discovery::{self, Discoverer, Manual, NetworkScan, Ssdp};
+use crate::error::{AuthFailed, Error};
+use crate::resources::{
+    self, Applications, AutoInstall, BehaviorInstance, BehaviorScript, ClipLight, DevicePower,
+    DeviceSoftwareUpdate, HealthReport, LightUpdate, NewBehaviorInstance, NewDevice, NewDevices,
+    NewResourceLink, NewRule, NewSchedule, NewSensor, ResourceLink, Room, Rule, Scene, Schedule,
+    SearchKind, Sensor, SoftwareUpdate, ZgpConnectivity, ZigbeeConnectivity,
+};
+#[cfg(feature = "eventstream")]
+use crate::resources::{DeviceSoftwareUpdateEvent, Event};
+use crate::session::Session;
+use futures::stream::{Stream, StreamExt};
+use std::future::Future;
+use std::net::Ipv4Addr;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use tokio::runtime::Runtime;
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME
+        .get_or_init(|| {
+            tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(1)
+                .thread_name("lightrary-blocking")
+                .enable_all()
+                .build()
+                .expect("failed to start the runtime of the blocking api")
+        })
+        .block_on(future)
+}
+
+/// Blocking [`DiscoveryBroker`](crate::discovery::DiscoveryBroker).
+#[derive(Debug)]
+pub struct DiscoveryBroker<D>(discovery::DiscoveryBroker<D>)
+where
+    D: Discoverer;
+
+impl<D> DiscoveryBroker<D>
+where
+    D: Discoverer<Device = resources::UnauthBridges> + Send + Sync,
+{
+    pub fn discover(&self) -> Result<UnauthBridges, Error> {
+        block_on(self.0.discover()).map(UnauthBridges)
+    }
+
+    /// See [`DiscoveryBroker::verified`](crate::discovery::DiscoveryBroker::verified).
+    pub fn verified(self) -> DiscoveryBroker<discovery::Verify<D>> {
+        DiscoveryBroker(self.0.verified())
+    }
+}
+
+#[cfg(feature = "mdns")]
+impl DiscoveryBroker<Mdns> {
+    pub fn mdns() -> Self {
+        Self(discovery::DiscoveryBroker::mdns())
+    }
+}
+
+#[cfg(feature = "cloud-discovery")]
+impl DiscoveryBroker<DiscoveryEndpoint> {
+    /// Note: should be limited to 1 request every 15 minutes.
+    pub fn discovery_endpoint() -> Self {
+        Self(discovery::DiscoveryBroker::discovery_endpoint())
+    }
+}
+
+impl DiscoveryBroker<Ssdp> {
+    pub fn ssdp() -> Self {
+        Self(discovery::DiscoveryBroker::ssdp())
+    }
+}
+
+impl DiscoveryBroker<NetworkScan> {
+    pub fn network_scan() -> Self {
+        Self(discovery::DiscoveryBroker::network_scan())
+    }
+}
+
+impl DiscoveryBroker<Manual> {
+    pub fn manual(ip: Ipv4Addr) -> Self {
+        Self(discovery::DiscoveryBroker::manual(ip))
+    }
+}
+
+impl<D> From<discovery::DiscoveryBroker<D>> for DiscoveryBroker<D>
+where
+    D: Discoverer,
+{
+    fn from(broker: discovery::DiscoveryBroker<D>) -> Self {
+        Self(broker)
+    }
+}
+
+/// Blocking [`UnauthBridges`](crate::resources::UnauthBridges).
+#[derive(Debug, Default)]
+pub struct UnauthBridges(resources::UnauthBridges);
+
+impl UnauthBridges {
+    /// Authenticates every bridge, sharing a single [`Session`] between them.
+    pub fn auth(self) -> (Vec<Bridge>, Vec<AuthFailed>) {
+        let (bridges, failed) = block_on(self.0.auth()).into();
+        (bridges.into_iter().map(Bridge).collect(), failed)
+    }
+
+    pub fn auth_with(self, session: &Session) -> (Vec<Bridge>, Vec<AuthFailed>) {
+        let (bridges, failed) = block_on(self.0.auth_with(session)).into();
+        (bridges.into_iter().map(Bridge).collect(), failed)
+    }
+
+    pub fn into_inner(self) -> resources::UnauthBridges {
+        self.0
+    }
+}
+
+impl IntoIterator for UnauthBridges {
+    type Item = UnauthBridge;
+    type IntoIter = std::iter::Map<
+        <resources::UnauthBridges as IntoIterator>::IntoIter,
+        fn(resources::UnauthBridge) -> UnauthBridge,
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter().map(UnauthBridge)
+    }
+}
+
+/// Blocking [`UnauthBridge`](crate::resources::UnauthBridge), derefs to the async one for
+/// everything that doesn't block.
+#[derive(Debug, Clone)]
+pub struct UnauthBridge(resources::UnauthBridge);
+
+// same signatures as the async api, which hands the bridge back on failure
+#[allow(clippy::result_large_err)]
+impl UnauthBridge {
+    pub fn auth(self) -> Result<Bridge, (resources::UnauthBridge, Error)> {
+        block_on(self.0.auth()).map(Bridge)
+    }
+
+    pub fn auth_with(self, session: &Session) -> Result<Bridge, (resources::UnauthBridge, Error)> {
+        block_on(self.0.auth_with(session)).map(Bridge)
+    }
+
+    pub fn into_inner(self) -> resources::UnauthBridge {
+        self.0
+    }
+}
+
+impl From<resources::UnauthBridge> for UnauthBridge {
+    fn from(bridge: resources::UnauthBridge) -> Self {
+        Self(bridge)
+    }
+}
+
+impl std::ops::Deref for UnauthBridge {
+    type Target = resources::UnauthBridge;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Blocking iterator over a stream of the async api, e.g. [`Bridge::events`].
+pub struct Iter<'a, T>(Pin<Box<dyn Stream<Item = Result<T, Error>> + Send + 'a>>);
+
+impl<T> Iterator for Iter<'_, T> {
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        block_on(self.0.next())
+    }
+}
+
+impl<T> std::fmt::Debug for Iter<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Iter").finish_non_exhaustive()
+    }
+}
+
+// generates the blocking version of async `Bridge` methods which take `&self`
+macro_rules! blocking {
+    ($(fn $name:ident(&self $(, $arg:ident: $ty:ty)*) -> $ret:ty;)*) => {
+        $(
+            #[doc = concat!(
+                "Blocking [`Bridge::", stringify!($name), "`](crate::resources::Bridge::",
+                stringify!($name), ")."
+            )]
+            pub fn $name(&self $(, $arg: $ty)*) -> $ret {
+                block_on(self.0.$name($($arg),*))
+            }
+        )*
+    };
+}
+
+/// Blocking [`Bridge`](crate::resources::Bridge), derefs to the async one for everything that
+/// doesn't block.
+#[derive(Debug)]
+pub struct Bridge(resources::Bridge);
+
+impl Bridge {
+    pub fn with_session(self, session: Session) -> Self {
+        Self(self.0.with_session(session))
+    }
+
+    pub fn with_key(self, app_key: String) -> Self {
+        Self(self.0.with_key(app_key))
+    }
+
+    pub fn with_watchdog(self, watchdog: resources::Watchdog) -> Self {
+        Self(self.0.with_watchdog(watchdog))
+    }
+
+    pub fn gen_key(self, app_name: &str, instance_name: &str) -> Result<(Self, String), Error> {
+        let (bridge, app_key) = block_on(self.0.gen_key(app_name, instance_name))?;
+        Ok((Self(bridge), app_key))
+    }
+
+    pub fn into_inner(self) -> resources::Bridge {
+        self.0
+    }
+
+    /// Blocking [`Bridge::events`](crate::resources::Bridge::events), only ends after an error that
+    /// reconnecting won't fix.
+    #[cfg(feature = "eventstream")]
+    pub fn events(&self) -> Iter<'_, Event> {
+        Iter(Box::pin(self.0.events()))
+    }
+
+    /// Blocking [`Bridge::device_software_update_events`](crate::resources::Bridge::device_software_update_events).
+    #[cfg(feature = "eventstream")]
+    pub fn device_software_update_events(&self) -> Iter<'_, DeviceSoftwareUpdateEvent> {
+        Iter(Box::pin(self.0.device_software_update_events()))
+    }
+
+    /// Blocking [`Bridge::search`](crate::resources::Bridge::search).
+    pub fn search<'a>(&'a self, kind: SearchKind, serials: &'a [&'a str]) -> Iter<'a, NewDevice> {
+        Iter(Box::pin(self.0.search(kind, serials)))
+    }
+
+    blocking! {
+        fn applications(&self) -> Result<Applications, Error>;
+        fn delete_application(&self, key: &str) -> Result<(), Error>;
+
+        fn backup(&self) -> Result<Backup, Error>;
+        fn restore(&self, backup: &Backup) -> Result<RestoreReport, Error>;
+
+        fn behavior_scripts(&self) -> Result<Vec<BehaviorScript>, Error>;
+        fn behavior_script_named(&self, name: &str) -> Result<Option<BehaviorScript>, Error>;
+        fn behavior_instances(&self) -> Result<Vec<BehaviorInstance>, Error>;
+        fn create_behavior_instance(&self, instance: &NewBehaviorInstance) -> Result<String, Error>;
+        fn set_behavior_instance_enabled(&self, id: &str, enabled: bool) -> Result<(), Error>;
+        fn set_behavior_instance_configuration(
+            &self,
+            id: &str,
+            configuration: &serde_json::Value
+        ) -> Result<(), Error>;
+        fn delete_behavior_instance(&self, id: &str) -> Result<(), Error>;
+
+        fn zigbee_connectivity(&self) -> Result<Vec<ZigbeeConnectivity>, Error>;
+        fn zgp_connectivity(&self) -> Result<Vec<ZgpConnectivity>, Error>;
+        fn device_power(&self) -> Result<Vec<DevicePower>, Error>;
+        fn health_report(&self) -> Result<HealthReport, Error>;
+
+        fn lights(&self) -> Result<Vec<ClipLight>, Error>;
+        fn update_light(&self, id: &str, update: &LightUpdate) -> Result<(), Error>;
+
+        fn resource_links(&self) -> Result<Vec<ResourceLink>, Error>;
+        fn create_resource_link(&self, link: &NewResourceLink) -> Result<String, Error>;
+        fn set_resource_link_links(&self, id: &str, links: &[String]) -> Result<(), Error>;
+        fn delete_resource_link(&self, id: &str) -> Result<(), Error>;
+
+        fn rooms(&self) -> Result<Vec<Room>, Error>;
+        fn update_grouped_light(&self, id: &str, update: &LightUpdate) -> Result<(), Error>;
+
+        fn rules(&self) -> Result<Vec<Rule>, Error>;
+        fn create_rule(&self, rule: &NewRule) -> Result<String, Error>;
+        fn update_rule(&self, id: &str, rule: &NewRule) -> Result<(), Error>;
+        fn set_rule_enabled(&self, id: &str, enabled: bool) -> Result<(), Error>;
+        fn delete_rule(&self, id: &str) -> Result<(), Error>;
+
+        fn scenes(&self) -> Result<Vec<Scene>, Error>;
+        fn recall_scene(&self, id: &str) -> Result<(), Error>;
+
+        fn schedules(&self) -> Result<Vec<Schedule>, Error>;
+        fn create_schedule(&self, schedule: &NewSchedule) -> Result<String, Error>;
+        fn set_schedule_enabled(&self, id: &str, enabled: bool) -> Result<(), Error>;
+        fn delete_schedule(&self, id: &str) -> Result<(), Error>;
+
+        fn start_search(&self, kind: SearchKind, serials: &[&str]) -> Result<(), Error>;
+        fn new_devices(&self, kind: SearchKind) -> Result<NewDevices, Error>;
+        fn touchlink(&self) -> Result<(), Error>;
+
+        fn sensors(&self) -> Result<Vec<Sensor>, Error>;
+        fn create_sensor(&self, sensor: &NewSensor) -> Result<String, Error>;
+        fn rename_sensor(&self, id: &str, name: &str) -> Result<(), Error>;
+        fn set_sensor_state(&self, id: &str, state: &serde_json::Value) -> Result<(), Error>;
+        fn set_sensor_config(&self, id: &str, config: &serde_json::Value) -> Result<(), Error>;
+        fn set_generic_status(&self, id: &str, status: i64) -> Result<(), Error>;
+        fn set_generic_flag(&self, id: &str, flag: bool) -> Result<(), Error>;
+        fn delete_sensor(&self, id: &str) -> Result<(), Error>;
+
+        fn software_update(&self) -> Result<SoftwareUpdate, Error>;
+        fn check_for_updates(&self) -> Result<(), Error>;
+        fn install_updates(&self) -> Result<(), Error>;
+        fn set_auto_install(&self, auto_install: &AutoInstall) -> Result<(), Error>;
+        fn device_software_updates(&self) -> Result<Vec<DeviceSoftwareUpdate>, Error>;
+    }
+}
+
+impl From<resources::Bridge> for Bridge {
+    fn from(bridge: resources::Bridge) -> Self {
+        Self(bridge)
+    }
+}
+
+impl std::ops::Deref for Bridge {
+    type Target = resources::Bridge;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
@@ -9,6 +9,8 @@
 //! whereas the hue endpoint does require a outside connection due to a cloud dependency
 //! and should limit requests to 1 request every 15 minutes. Manual should only be considered
 //! if neither mDNS nor the endpoint show your bridge(s), manual also doesn't need a request limit.
+//! On networks where mDNS is blocked and there's no outside connection a network scan finds
+//! bridges without knowing their IP, at the cost of probing every address in a range.
 //!
 //! [tower-limit](https://crates.io/crates/tower-limit) is a great request limiter for the `DiscoveryBroker<DiscoveryEndpoint>`.
 //!
@@ -16,6 +18,8 @@
 //! |----------|--------------|----------|
 //! | mDNS     | Truely local: no outside connection (wanted or available) without a request limit.  | mDNS over discovery-endpoint |
 //! | Discovery-endpoint | Cloud dependant, should limit requests to 1 request every 15 minutes. | Discovery-endpoint over manual |
+//! | SSDP | Truely local, also answered by older bridges. Some networks block multicast. | mDNS over SSDP |
+//! | Network scan | Truely local, probes every address so it's slow and noisy. | Manual over network scan |
 //! | Manual | As a last resort when all others fail, no request limit needed. | Fallback |
 //!
 //! # Examples
@@ -52,21 +56,73 @@
 //! # #[tokio::main]
 //! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //! let broker = DiscoveryBroker::manual("192.168.50.173".parse()?);
-//! // note: manual only supports one bridge at a time, possible list
-//! // might be supported in the future.
-//! let bridge = broker.discover().await?;
+//! let bridges = broker.discover().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Example using SSDP:
+//! ```no_run
+//! use lightrary::discovery::DiscoveryBroker;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let broker = DiscoveryBroker::ssdp();
+//! let bridges = broker.discover().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Discovered bridges aren't contacted by most protocols, use
+//! [`DiscoveryBroker::verified`] to leave out bridges that can't be reached or aren't supported.
+//!
+//! To keep track of bridges being plugged in, unplugged or moved to another address use a
+//! [`DiscoveryWatch`] instead.
+//!
+//! Example scanning the local subnets:
+//! ```no_run
+//! use lightrary::discovery::DiscoveryBroker;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let broker = DiscoveryBroker::network_scan();
+//! let bridges = broker.discover().await?;
 //! # Ok(())
 //! # }
 //! ```
 
-use crate::error::Error;
-use crate::resources::{UnauthBridge, UnauthBridges};
+use crate::error::{Error, Redacted};
+use crate::resources::{fetch_config, Capability, UnauthBridge, UnauthBridges};
+use crate::retry::RetryPolicy;
+use crate::session::Session;
 use async_trait::async_trait;
-use futures_util::{pin_mut, stream::StreamExt};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use futures_util::{future, stream, stream::StreamExt};
+use if_addrs::{IfAddr, Interface};
+#[cfg(feature = "mdns")]
 use mdns::{Record, RecordKind};
-use std::net::Ipv4Addr;
+use serde::Deserialize;
+use std::fmt;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::str::FromStr;
+use std::sync::Arc;
 use std::{net::IpAddr, time::Duration};
+use tracing::Instrument;
+
+#[cfg(feature = "mdns")]
+mod watch;
+
+pub use ipnet::Ipv4Net;
+#[cfg(feature = "mdns")]
+pub use watch::{DiscoveryEvent, DiscoveryWatch};
+
+#[cfg(feature = "mdns")]
+const MDNS_SERVICE: &str = "_hue._tcp.local";
+
+// type erased discoverer for the parts of the crate which hold on to multiple protocols
+pub(crate) type BoxDiscoverer =
+    Arc<dyn Fn() -> BoxFuture<'static, Result<UnauthBridges, Error>> + Send + Sync>;
 
 /// Interchangeable discovery protocol for the `DiscoveryBroker`.
 #[async_trait]
@@ -78,9 +134,11 @@ pub trait Discoverer {
 
 /// Discovers all bridges present on your local network through Multicast DNS or
 /// [mDNS](https://en.wikipedia.org/wiki/Multicast_DNS) for short.
+#[cfg(feature = "mdns")]
 #[derive(Debug)]
-pub struct Mdns(UnauthBridges);
+pub struct Mdns;
 
+#[cfg(feature = "mdns")]
 #[async_trait]
 impl Discoverer for Mdns {
     type Device = UnauthBridges;
@@ -89,13 +147,14 @@ impl Discoverer for Mdns {
     // can't really test because I only have one bridge.
     // TODO: find a way to support a longer search duration without early return
     async fn discover(&self) -> Result<Self::Device, Error> {
-        let stream = mdns::discover::all("_hue._tcp.local", Duration::from_millis(150))?.listen();
+        let stream = mdns::discover::all(MDNS_SERVICE, Duration::from_millis(150))?.listen();
         let mut bridges = UnauthBridges::default();
-        pin_mut!(stream);
+        futures_util::pin_mut!(stream);
 
         match stream.next().await {
             Some(Ok(response)) => {
                 let addr = response.records().filter_map(self::to_ip_addr).next();
+                tracing::debug!(?addr, "mdns response");
 
                 if let Some(addr) = addr {
                     match addr {
@@ -103,19 +162,24 @@ impl Discoverer for Mdns {
                             id: None,
                             ip,
                             port: 443,
+                            config: None,
                         }),
                         _ => unreachable!("ipv6 not yet supported."),
                     }
                 }
             }
             Some(Err(e)) => return Err(e.into()),
-            None => return Ok(bridges),
+            None => {
+                tracing::debug!("no mdns response");
+                return Ok(bridges);
+            }
         }
 
         Ok(bridges)
     }
 }
 
+#[cfg(feature = "mdns")]
 fn to_ip_addr(record: &Record) -> Option<IpAddr> {
     match record.kind {
         RecordKind::A(addr) => Some(addr.into()),
@@ -124,32 +188,218 @@ fn to_ip_addr(record: &Record) -> Option<IpAddr> {
     }
 }
 
+const SSDP_ADDR: (Ipv4Addr, u16) = (Ipv4Addr::new(239, 255, 255, 250), 1900);
+const SSDP_SEARCH_DURATION: Duration = Duration::from_secs(3);
+const M_SEARCH: &str = "M-SEARCH * HTTP/1.1\r\n\
+    HOST: 239.255.255.250:1900\r\n\
+    MAN: \"ssdp:discover\"\r\n\
+    MX: 2\r\n\
+    ST: ssdp:all\r\n\r\n";
+
+/// Discovers bridges on your local network through the
+/// [SSDP](https://en.wikipedia.org/wiki/Simple_Service_Discovery_Protocol) part of UPnP,
+/// which older bridges answer as well.
+///
+/// Listens for responses for 3 seconds, then reads the bridge id from the `description.xml`
+/// of every bridge that answered.
+#[derive(Debug, Clone, Default)]
+pub struct Ssdp {
+    session: Option<Session>,
+}
+
+impl Ssdp {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetches the descriptions through your own session, which has to allow plain http
+    /// (see [`SessionBuilder::https_only`](crate::session::SessionBuilder::https_only)).
+    pub fn with_session(mut self, session: Session) -> Self {
+        self.session = Some(session);
+        self
+    }
+}
+
+#[async_trait]
+impl Discoverer for Ssdp {
+    type Device = UnauthBridges;
+
+    async fn discover(&self) -> Result<Self::Device, Error> {
+        let socket = tokio::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+        socket.send_to(M_SEARCH.as_bytes(), SSDP_ADDR).await?;
+
+        // every device on the network answers, and bridges answer once per service
+        let deadline = tokio::time::Instant::now() + SSDP_SEARCH_DURATION;
+        let mut locations = Vec::<(Ipv4Addr, String)>::new();
+        let mut buf = [0; 2048];
+        loop {
+            let (len, from) =
+                match tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+                    Ok(Ok(res)) => res,
+                    // e.g. an icmp error caused by another device, the other answers still count
+                    Ok(Err(e)) => {
+                        tracing::debug!(error = %e, "receiving ssdp response failed");
+                        continue;
+                    }
+                    Err(_) => break,
+                };
+            let ip = match from.ip() {
+                IpAddr::V4(ip) => ip,
+                IpAddr::V6(_) => continue,
+            };
+            let response = String::from_utf8_lossy(&buf[..len]);
+            if let Some(location) = hue_location(&response) {
+                tracing::trace!(%ip, location, "ssdp response");
+                if !locations.iter().any(|(known, _)| *known == ip) {
+                    locations.push((ip, location.to_owned()));
+                }
+            }
+        }
+
+        tracing::debug!(bridges = locations.len(), "ssdp search finished");
+        // descriptions are served over plain http
+        let session = match &self.session {
+            Some(session) => session.clone(),
+            None => Session::builder().https_only(false).build()?,
+        };
+        let bridges = future::join_all(
+            locations
+                .into_iter()
+                .map(|(ip, location)| describe(&session, ip, location)),
+        )
+        .await;
+        Ok(UnauthBridges(bridges.into_iter().flatten().collect()))
+    }
+}
+
+// bridges identify themselves with a `hue-bridgeid` header, older ones only through
+// the `IpBridge` server name
+fn hue_location(response: &str) -> Option<&str> {
+    let mut location = None;
+    let mut is_hue = false;
+    for (name, value) in response.lines().filter_map(|line| line.split_once(':')) {
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("location") {
+            location = Some(value);
+        } else if name.eq_ignore_ascii_case("hue-bridgeid")
+            || (name.eq_ignore_ascii_case("server") && value.contains("IpBridge"))
+        {
+            is_hue = true;
+        }
+    }
+    location.filter(|_| is_hue)
+}
+
+#[derive(Deserialize)]
+struct Description {
+    device: DescriptionDevice,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DescriptionDevice {
+    model_name: String,
+    serial_number: String,
+}
+
+async fn describe(session: &Session, ip: Ipv4Addr, location: String) -> Option<UnauthBridge> {
+    let xml = session
+        .send(session.get(location))
+        .await
+        .ok()?
+        .text()
+        .await
+        .ok()?;
+    Some(UnauthBridge {
+        id: Some(description_bridge_id(&xml)?),
+        ip,
+        port: 443,
+        config: None,
+    })
+}
+
+fn description_bridge_id(xml: &str) -> Option<String> {
+    let device = quick_xml::de::from_str::<Description>(xml).ok()?.device;
+    let is_mac = device.serial_number.len() == 12
+        && device.serial_number.chars().all(|c| c.is_ascii_hexdigit());
+    if !device.model_name.contains("hue bridge") || !is_mac {
+        return None;
+    }
+
+    // the serial number is the mac address, the bridge id inserts `fffe` in the middle of it
+    let serial = device.serial_number.to_lowercase();
+    Some(format!("{}fffe{}", &serial[..6], &serial[6..]))
+}
+
 /// Discovery protocol that uses Philips' discovery endpoint: <https://discovery.meethue.com>
 ///
 /// Note: should be limited to 1 request every 15 minutes.
-#[derive(Debug)]
-pub struct DiscoveryEndpoint;
+#[cfg(feature = "cloud-discovery")]
+#[derive(Debug, Clone, Default)]
+pub struct DiscoveryEndpoint {
+    session: Option<Session>,
+}
+
+#[cfg(feature = "cloud-discovery")]
+impl DiscoveryEndpoint {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
+    pub fn with_session(mut self, session: Session) -> Self {
+        self.session = Some(session);
+        self
+    }
+}
+
+#[cfg(feature = "cloud-discovery")]
 #[async_trait]
 impl Discoverer for DiscoveryEndpoint {
     type Device = UnauthBridges;
 
     async fn discover(&self) -> Result<Self::Device, Error> {
-        reqwest::get("https://discovery.meethue.com")
+        // unlike bridges the endpoint has a proper certificate
+        let session = match &self.session {
+            Some(session) => session.clone(),
+            None => Session::builder().accept_invalid_certs(false).build()?,
+        };
+        let bridges = session
+            .send(session.get("https://discovery.meethue.com"))
             .await?
+            .error_for_status()?
             .json::<UnauthBridges>()
-            .await
-            .map_err(|e| e.into())
+            .await?;
+        tracing::debug!(bridges = bridges.0.len(), "discovery endpoint responded");
+        Ok(bridges)
     }
 }
 
-/// Manually discover your bridge on a local network.
+/// Manually discover your bridge(s) on a local network.
 ///
 /// You can find your bridge's IP on your router. If not found check the connectivity
 /// of the bridge and see if the second LED is on. (which signifies the network connection state)
 ///
-/// An [`Ipv4Addr`](std::net::Ipv4Addr) can also be converted to a `DiscoveryBroker<Manual>`
-/// using the [`From`](std::convert::From) trait.
+/// Every address is validated by fetching the bridge configuration, which also fills in the
+/// bridge ids. Addresses that don't respond like a bridge are left out, discovery only fails
+/// when none of them do.
+///
+/// ```no_run
+/// use lightrary::discovery::{DiscoveryBroker, Manual};
+/// use std::net::Ipv4Addr;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let manual = Manual::new()
+///     .with_ip(Ipv4Addr::new(192, 168, 50, 173))
+///     .with_addr("10.0.0.2:8443".parse()?)
+///     .with_host("hue-upstairs.lan");
+/// let bridges = DiscoveryBroker::from(manual).discover().await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// An [`Ipv4Addr`] can also be converted to a `DiscoveryBroker<Manual>`
+/// using the [`From`] trait.
 ///
 /// ```no_run
 /// use lightrary::discovery::DiscoveryBroker;
@@ -161,8 +411,8 @@ impl Discoverer for DiscoveryEndpoint {
 /// # }
 /// ```
 ///
-/// You can also directly convert a ip str into a `DiscoveryBroker<Manual>` using
-/// [`FromStr`](std::str::FromStr):
+/// You can also directly convert a ip str, optionally with a port, into a
+/// `DiscoveryBroker<Manual>` using [`FromStr`]:
 /// ```no_run
 /// use lightrary::discovery::{DiscoveryBroker, Manual};
 ///
@@ -171,18 +421,333 @@ impl Discoverer for DiscoveryEndpoint {
 /// # Ok(())
 /// # }
 /// ```
-#[derive(Debug)]
-pub struct Manual(Ipv4Addr);
+#[derive(Debug, Clone, Default)]
+pub struct Manual {
+    candidates: Vec<Candidate>,
+    session: Option<Session>,
+}
+
+#[derive(Debug, Clone)]
+enum Candidate {
+    Addr(SocketAddr),
+    Host(String),
+}
+
+impl Manual {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_ip(self, ip: Ipv4Addr) -> Self {
+        self.with_addr(SocketAddr::from((ip, 443)))
+    }
+
+    /// Adds an address with a non-default port, e.g. a bridge behind port forwarding.
+    pub fn with_addr(mut self, addr: SocketAddr) -> Self {
+        self.candidates.push(Candidate::Addr(addr));
+        self
+    }
+
+    /// Adds a DNS hostname, optionally with a port, e.g. `hue.lan` or `hue.lan:8443`.
+    pub fn with_host(mut self, host: &str) -> Self {
+        self.candidates.push(Candidate::Host(host.to_owned()));
+        self
+    }
+
+    /// Validates the candidates through your own session instead of a new one.
+    pub fn with_session(mut self, session: Session) -> Self {
+        self.session = Some(session);
+        self
+    }
+}
+
+impl FromIterator<Ipv4Addr> for Manual {
+    fn from_iter<I: IntoIterator<Item = Ipv4Addr>>(iter: I) -> Self {
+        iter.into_iter().fold(Self::new(), Self::with_ip)
+    }
+}
+
+impl FromIterator<SocketAddr> for Manual {
+    fn from_iter<I: IntoIterator<Item = SocketAddr>>(iter: I) -> Self {
+        iter.into_iter().fold(Self::new(), Self::with_addr)
+    }
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Candidate::Addr(addr) => addr.fmt(f),
+            Candidate::Host(host) => host.fmt(f),
+        }
+    }
+}
 
 #[async_trait]
 impl Discoverer for Manual {
-    type Device = UnauthBridge;
+    type Device = UnauthBridges;
 
     async fn discover(&self) -> Result<Self::Device, Error> {
-        Ok(UnauthBridge {
-            id: None,
-            ip: self.0,
-            port: 443,
+        let session = match &self.session {
+            Some(session) => session.clone(),
+            None => Session::new()?,
+        };
+        let results = future::join_all(self.candidates.iter().map(|c| validate(&session, c))).await;
+
+        let mut bridges = UnauthBridges::default();
+        let mut err = None;
+        for result in results {
+            match result {
+                // the same bridge might be listed by both its hostname and ip
+                Ok(bridge) if bridges.0.iter().any(|known| known.id == bridge.id) => {}
+                Ok(bridge) => bridges.0.push(bridge),
+                Err(e) => {
+                    tracing::debug!(error = %Redacted(&e), "manual candidate isn't a bridge");
+                    err = err.or(Some(e));
+                }
+            }
+        }
+        match err {
+            Some(e) if bridges.0.is_empty() => Err(e),
+            _ => Ok(bridges),
+        }
+    }
+}
+
+async fn validate(session: &Session, candidate: &Candidate) -> Result<UnauthBridge, Error> {
+    let addr = match candidate {
+        Candidate::Addr(addr) => Some(*addr),
+        Candidate::Host(host) if host.contains(':') => tokio::net::lookup_host(host)
+            .await?
+            .find(SocketAddr::is_ipv4),
+        Candidate::Host(host) => tokio::net::lookup_host((host.as_str(), 443))
+            .await?
+            .find(SocketAddr::is_ipv4),
+    };
+    let addr = match addr {
+        Some(SocketAddr::V4(addr)) => addr,
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "{} has no ipv4 address, ipv6 isn't supported yet",
+                    candidate
+                ),
+            )
+            .into())
+        }
+    };
+
+    let config = fetch_config(session, addr).await?;
+    Ok(UnauthBridge {
+        id: Some(config.bridge_id().to_lowercase()),
+        ip: *addr.ip(),
+        port: addr.port(),
+        config: Some(config),
+    })
+}
+
+/// Discovers bridges by probing `/api/0/config` on every address in one or more ranges, for
+/// networks where mDNS is blocked and there's no outside connection for the discovery endpoint.
+///
+/// Without any ranges the local subnets of this host are scanned.
+///
+/// ```no_run
+/// use lightrary::discovery::{DiscoveryBroker, NetworkScan};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let scan = NetworkScan::new()
+///     .with_range("10.0.20.0/24".parse()?)
+///     .with_concurrency(32)
+///     .with_timeout(Duration::from_millis(500));
+/// let bridges = DiscoveryBroker::from(scan).discover().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct NetworkScan {
+    ranges: Vec<Ipv4Net>,
+    concurrency: usize,
+    timeout: Duration,
+}
+
+/// Scans the local subnets, 64 addresses at a time with a 1s timeout.
+impl Default for NetworkScan {
+    fn default() -> Self {
+        Self {
+            ranges: Vec::new(),
+            concurrency: 64,
+            timeout: Duration::from_secs(1),
+        }
+    }
+}
+
+impl NetworkScan {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a range to scan, e.g. `192.168.1.0/24`, replacing the local subnets.
+    pub fn with_range(mut self, range: Ipv4Net) -> Self {
+        self.ranges.push(range);
+        self
+    }
+
+    /// Maximum number of addresses probed at the same time.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// How long to wait for an address to respond before moving on.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[async_trait]
+impl Discoverer for NetworkScan {
+    type Device = UnauthBridges;
+
+    async fn discover(&self) -> Result<Self::Device, Error> {
+        let ranges = match self.ranges.is_empty() {
+            true => local_subnets()?,
+            false => self.ranges.clone(),
+        };
+        let mut hosts = ranges.iter().flat_map(Ipv4Net::hosts).collect::<Vec<_>>();
+        hosts.sort();
+        hosts.dedup();
+        tracing::debug!(?ranges, hosts = hosts.len(), "scanning network");
+
+        // nothing listens on most addresses, retrying them would only slow the scan down
+        let session = Session::builder()
+            .connect_timeout(self.timeout)
+            .timeout(self.timeout)
+            .retry_policy(RetryPolicy::none())
+            .build()?;
+        let mut bridges = stream::iter(hosts)
+            .map(|ip| probe(&session, ip))
+            .buffer_unordered(self.concurrency)
+            .filter_map(future::ready)
+            .collect::<Vec<_>>()
+            .await;
+        bridges.sort_by_key(|bridge| bridge.ip);
+
+        Ok(UnauthBridges(bridges))
+    }
+}
+
+async fn probe(session: &Session, ip: Ipv4Addr) -> Option<UnauthBridge> {
+    let config = fetch_config(session, SocketAddrV4::new(ip, 443))
+        .await
+        .ok()?;
+
+    config.is_hue_bridge().then(|| UnauthBridge {
+        id: Some(config.bridge_id().to_lowercase()),
+        ip,
+        port: 443,
+        config: Some(config),
+    })
+}
+
+fn local_subnets() -> Result<Vec<Ipv4Net>, Error> {
+    Ok(subnets(if_addrs::get_if_addrs()?))
+}
+
+// subnets wider than a /24 would take ages to scan, only the /24 around our own address
+// is scanned for those. point-to-point links (vpn tunnels) have no broadcast address and
+// there's nothing else on a /31 or /32, those aren't scanned at all
+fn subnets(interfaces: Vec<Interface>) -> Vec<Ipv4Net> {
+    interfaces
+        .into_iter()
+        .filter_map(|interface| match interface.addr {
+            IfAddr::V4(addr) if !addr.ip.is_loopback() && !addr.ip.is_link_local() => {
+                addr.broadcast?;
+                let net = Ipv4Net::with_netmask(addr.ip, addr.netmask).ok()?;
+                if net.prefix_len() >= 31 {
+                    return None;
+                }
+                Ipv4Net::new(addr.ip, net.prefix_len().max(24)).ok()
+            }
+            _ => None,
+        })
+        .map(|net| net.trunc())
+        .collect()
+}
+
+/// Checks every bridge another discovery protocol found before returning it, see
+/// [`DiscoveryBroker::verified`].
+#[derive(Debug)]
+pub struct Verify<D> {
+    discoverer: D,
+    min_api_version: (u32, u32, u32),
+    timeout: Duration,
+    session: Option<Session>,
+}
+
+#[async_trait]
+impl<D> Discoverer for Verify<D>
+where
+    D: Discoverer + Send + Sync,
+    D::Device: Into<UnauthBridges> + Send,
+{
+    type Device = UnauthBridges;
+
+    async fn discover(&self) -> Result<Self::Device, Error> {
+        let found = self.discoverer.discover().await?.into();
+        // retrying would only stretch the timeout, an unreachable bridge isn't returned anyway
+        let session = match &self.session {
+            Some(session) => session.clone().with_retry_policy(RetryPolicy::none()),
+            None => Session::builder()
+                .retry_policy(RetryPolicy::none())
+                .build()?,
+        };
+        let verified = future::join_all(
+            found
+                .into_iter()
+                .map(|bridge| self.verify(&session, bridge)),
+        )
+        .await;
+
+        Ok(UnauthBridges(verified.into_iter().flatten().collect()))
+    }
+}
+
+impl<D> Verify<D> {
+    async fn verify(&self, session: &Session, bridge: UnauthBridge) -> Option<UnauthBridge> {
+        // unreachable bridges are mostly stale discovery endpoint entries of bridges which
+        // moved or were unplugged
+        // timed out here rather than by the session, so it also holds for your own session
+        // and transport
+        let config = tokio::time::timeout(self.timeout, fetch_config(session, bridge.addr()))
+            .await
+            .ok()?
+            .ok()?;
+        // the address belongs to another bridge by now
+        let moved = bridge
+            .id
+            .as_deref()
+            .is_some_and(|id| !id.eq_ignore_ascii_case(config.bridge_id()));
+        let supported = config
+            .api_version_parts()
+            .is_some_and(|version| version >= self.min_api_version);
+        if moved || !supported || !config.is_hue_bridge() {
+            tracing::debug!(
+                ip = %bridge.ip,
+                moved,
+                supported,
+                api_version = config.api_version(),
+                "leaving out unverified bridge"
+            );
+            return None;
+        }
+
+        Some(UnauthBridge {
+            id: Some(config.bridge_id().to_lowercase()),
+            config: Some(config),
+            ..bridge
         })
     }
 }
@@ -193,6 +758,8 @@ impl Discoverer for Manual {
 /// |--------------------|------------------------------------------------------------------------------|
 /// | mDNS               | [`DiscoveryBroker::mdns`](DiscoveryBroker::mdns)                             |
 /// | Discovery Endpoint | [`DiscoveryBroker::discovery_endpoint`](DiscoveryBroker::discovery_endpoint) |
+/// | SSDP               | [`DiscoveryBroker::ssdp`](DiscoveryBroker::ssdp)                             |
+/// | Network scan       | [`DiscoveryBroker::network_scan`](DiscoveryBroker::network_scan)             |
 /// | Manual             | [`DiscoveryBroker::manual`](DiscoveryBroker::manual)                         |
 #[derive(Debug)]
 pub struct DiscoveryBroker<D>
@@ -206,52 +773,189 @@ impl<D> DiscoveryBroker<D>
 where
     D: Discoverer,
 {
-    /// Use the currently selected discovery method; mDNS, discovery-endpoint or manual,
-    /// to discover your bridge(s) on your local network.
+    /// Use the currently selected discovery method; mDNS, discovery endpoint, SSDP, network
+    /// scan or manual, optionally verified, to discover your bridge(s) on your local network.
     ///
     /// Either yields the found device(s) or network connectivity errors or mDNS errors.
     pub async fn discover(&self) -> Result<D::Device, Error> {
-        self.discoverer.discover().await
+        let protocol = std::any::type_name::<D>()
+            .rsplit("::")
+            .next()
+            .unwrap_or_default();
+        let res = self
+            .discoverer
+            .discover()
+            .instrument(tracing::debug_span!("discover", protocol))
+            .await;
+        if let Err(e) = &res {
+            tracing::warn!(protocol, error = %Redacted(e), "discovery failed");
+        }
+        res
+    }
+}
+
+impl<D> DiscoveryBroker<D>
+where
+    D: Discoverer + Send + Sync,
+    D::Device: Into<UnauthBridges> + Send,
+{
+    /// Verifies every discovered bridge concurrently before returning it, leaving out
+    /// bridges that can't be reached, aren't Hue bridges, answer with another bridge id than
+    /// discovered (stale discovery endpoint entries) or run an api version older than 1.48.
+    ///
+    /// Verified bridges come with their [configuration](UnauthBridge::config), e.g. to
+    /// check whether they're factory new.
+    ///
+    /// ```no_run
+    /// use lightrary::discovery::DiscoveryBroker;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let broker = DiscoveryBroker::discovery_endpoint().verified();
+    /// for bridge in broker.discover().await? {
+    ///     let config = bridge.config().expect("verified bridges have a config");
+    ///     println!("{} factory new: {}", config.name(), config.factory_new());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn verified(self) -> DiscoveryBroker<Verify<D>> {
+        DiscoveryBroker {
+            discoverer: Verify {
+                discoverer: self.discoverer,
+                // most of this crate is built on the CLIP v2 api
+                min_api_version: Capability::ClipV2.since(),
+                timeout: Duration::from_secs(3),
+                session: None,
+            },
+        }
     }
 }
 
+impl<D> DiscoveryBroker<Verify<D>>
+where
+    D: Discoverer + Send + Sync,
+    D::Device: Into<UnauthBridges> + Send,
+{
+    /// Leaves out bridges running an older api version, e.g. `(1, 24, 0)` to also accept
+    /// bridges which only speak the v1 api.
+    pub fn with_min_api_version(mut self, version: (u32, u32, u32)) -> Self {
+        self.discoverer.min_api_version = version;
+        self
+    }
+
+    /// How long to wait for a bridge to respond, 3 seconds by default.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.discoverer.timeout = timeout;
+        self
+    }
+
+    /// Verifies bridges through your own session, e.g. one bound to the interface on the
+    /// lighting network with [`local_address`](crate::session::SessionBuilder::local_address).
+    /// Requests aren't retried and are cut off after the [timeout](Self::with_timeout), the
+    /// rest of the session is used as is.
+    pub fn with_session(mut self, session: Session) -> Self {
+        self.discoverer.session = Some(session);
+        self
+    }
+}
+
+impl<D> DiscoveryBroker<D>
+where
+    D: Discoverer + Send + Sync + 'static,
+    D::Device: Into<UnauthBridges>,
+{
+    pub(crate) fn boxed(self) -> BoxDiscoverer {
+        let broker = Arc::new(self);
+        Arc::new(move || {
+            let broker = broker.clone();
+            async move { broker.discover().await.map(Into::into) }.boxed()
+        })
+    }
+}
+
+#[cfg(feature = "mdns")]
 impl DiscoveryBroker<Mdns> {
     /// Creates a discovery broker with the mDNS protocol.
     pub fn mdns() -> Self {
-        let discoverer = Mdns(UnauthBridges::default());
+        let discoverer = Mdns;
 
         Self { discoverer }
     }
 }
 
+#[cfg(feature = "cloud-discovery")]
 impl DiscoveryBroker<DiscoveryEndpoint> {
     /// Creates a discovery broker with the discovery-endpoint access.
     ///
     /// Note: should be limited to 1 request every 15 minutes.
     pub fn discovery_endpoint() -> Self {
-        let discoverer = DiscoveryEndpoint;
+        let discoverer = DiscoveryEndpoint::new();
+
+        Self { discoverer }
+    }
+}
+
+#[cfg(feature = "cloud-discovery")]
+impl From<DiscoveryEndpoint> for DiscoveryBroker<DiscoveryEndpoint> {
+    fn from(discoverer: DiscoveryEndpoint) -> Self {
+        Self { discoverer }
+    }
+}
+
+impl DiscoveryBroker<Ssdp> {
+    /// Creates a discovery broker with the SSDP protocol.
+    pub fn ssdp() -> Self {
+        let discoverer = Ssdp::new();
+
+        Self { discoverer }
+    }
+}
+
+impl From<Ssdp> for DiscoveryBroker<Ssdp> {
+    fn from(discoverer: Ssdp) -> Self {
+        Self { discoverer }
+    }
+}
+
+impl DiscoveryBroker<NetworkScan> {
+    /// Creates a discovery broker which scans the local subnets, use
+    /// [`From<NetworkScan>`](NetworkScan) to scan other ranges.
+    pub fn network_scan() -> Self {
+        let discoverer = NetworkScan::default();
 
         Self { discoverer }
     }
 }
 
+impl From<NetworkScan> for DiscoveryBroker<NetworkScan> {
+    fn from(discoverer: NetworkScan) -> Self {
+        Self { discoverer }
+    }
+}
+
 impl DiscoveryBroker<Manual> {
     /// Creates a discovery broker with the manually entered IP connecting
-    /// straight to your bridge.
+    /// straight to your bridge, use [`From<Manual>`](Manual) for multiple bridges,
+    /// other ports or hostnames.
     ///
     /// Note: only use this if no other protocol works.
     pub fn manual(ip: Ipv4Addr) -> Self {
-        let discoverer = Manual(ip);
+        let discoverer = Manual::new().with_ip(ip);
+
+        Self { discoverer }
+    }
+}
 
+impl From<Manual> for DiscoveryBroker<Manual> {
+    fn from(discoverer: Manual) -> Self {
         Self { discoverer }
     }
 }
 
 impl From<Ipv4Addr> for DiscoveryBroker<Manual> {
     fn from(ip: Ipv4Addr) -> Self {
-        Self {
-            discoverer: Manual(ip),
-        }
+        Self::manual(ip)
     }
 }
 
@@ -259,8 +963,255 @@ impl FromStr for DiscoveryBroker<Manual> {
     type Err = std::net::AddrParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self {
-            discoverer: Manual(s.parse()?),
-        })
+        let discoverer = match s.parse::<SocketAddr>() {
+            Ok(addr) => Manual::new().with_addr(addr),
+            Err(_) => Manual::new().with_ip(s.parse()?),
+        };
+
+        Ok(Self { discoverer })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{Request, Response, StatusCode, Transport};
+    use if_addrs::Ifv4Addr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn interface(ip: &str, netmask: &str, broadcast: Option<&str>) -> Interface {
+        Interface {
+            name: "eth0".to_owned(),
+            addr: IfAddr::V4(Ifv4Addr {
+                ip: ip.parse().unwrap(),
+                netmask: netmask.parse().unwrap(),
+                broadcast: broadcast.map(|b| b.parse().unwrap()),
+            }),
+        }
+    }
+
+    fn net(net: &str) -> Ipv4Net {
+        net.parse().unwrap()
+    }
+
+    const DESCRIPTION: &str = r#"<?xml version="1.0" encoding="UTF-8" ?>
+<root xmlns="urn:schemas-upnp-org:device-1-0">
+<specVersion><major>1</major><minor>0</minor></specVersion>
+<URLBase>http://192.168.1.2:80/</URLBase>
+<device>
+<deviceType>urn:schemas-upnp-org:device:Basic:1</deviceType>
+<friendlyName>Philips hue (192.168.1.2)</friendlyName>
+<manufacturer>Signify</manufacturer>
+<modelDescription>Philips hue Personal Wireless Lighting</modelDescription>
+<modelName>Philips hue bridge 2015</modelName>
+<modelNumber>BSB002</modelNumber>
+<serialNumber>00178829D1B4</serialNumber>
+<UDN>uuid:2f402f80-da50-11e1-9b23-00178829d1b4</UDN>
+</device>
+</root>"#;
+
+    #[test]
+    fn hue_location_of_bridges() {
+        let response = "HTTP/1.1 200 OK\r\n\
+            CACHE-CONTROL: max-age=100\r\n\
+            EXT:\r\n\
+            LOCATION: http://192.168.1.2:80/description.xml\r\n\
+            SERVER: Hue/1.0 UPnP/1.0 IpBridge/1.56.0\r\n\
+            hue-bridgeid: 00178829D1B4\r\n\
+            ST: upnp:rootdevice\r\n\r\n";
+        assert_eq!(
+            hue_location(response),
+            Some("http://192.168.1.2:80/description.xml")
+        );
+
+        // older bridges without the `hue-bridgeid` header
+        let response = "HTTP/1.1 200 OK\r\n\
+            location: http://192.168.1.3:80/description.xml\r\n\
+            server: Linux/3.14.0 UPnP/1.0 IpBridge/1.16.0\r\n\r\n";
+        assert_eq!(
+            hue_location(response),
+            Some("http://192.168.1.3:80/description.xml")
+        );
+    }
+
+    #[test]
+    fn hue_location_of_other_devices() {
+        let response = "HTTP/1.1 200 OK\r\n\
+            LOCATION: http://192.168.1.50:1400/xml/device_description.xml\r\n\
+            SERVER: Linux UPnP/1.0 Sonos/70.3-35220\r\n\r\n";
+        assert_eq!(hue_location(response), None);
+        // a bridge without a location is of no use either
+        assert_eq!(
+            hue_location("HTTP/1.1 200 OK\r\nhue-bridgeid: 0017\r\n\r\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn bridge_id_from_description() {
+        assert_eq!(
+            description_bridge_id(DESCRIPTION).as_deref(),
+            Some("001788fffe29d1b4")
+        );
+    }
+
+    #[test]
+    fn bridge_id_from_other_descriptions() {
+        let other = DESCRIPTION.replace("Philips hue bridge 2015", "Sonos One");
+        assert_eq!(description_bridge_id(&other), None);
+        let no_mac = DESCRIPTION.replace("00178829D1B4", "RINCON_1234");
+        assert_eq!(description_bridge_id(&no_mac), None);
+        assert_eq!(description_bridge_id("not xml"), None);
+    }
+
+    #[test]
+    fn subnets_of_local_interfaces() {
+        let subnets = subnets(vec![
+            interface("192.168.1.23", "255.255.255.0", Some("192.168.1.255")),
+            interface("10.0.20.5", "255.255.255.192", Some("10.0.20.63")),
+        ]);
+        assert_eq!(subnets, vec![net("192.168.1.0/24"), net("10.0.20.0/26")]);
+    }
+
+    #[test]
+    fn wide_subnets_are_narrowed_to_a_24() {
+        let subnets = subnets(vec![interface(
+            "10.1.2.3",
+            "255.0.0.0",
+            Some("10.255.255.255"),
+        )]);
+        assert_eq!(subnets, vec![net("10.1.2.0/24")]);
+    }
+
+    #[test]
+    fn loopback_and_link_local_are_skipped() {
+        let subnets = subnets(vec![
+            interface("127.0.0.1", "255.0.0.0", Some("127.255.255.255")),
+            interface("169.254.10.20", "255.255.0.0", Some("169.254.255.255")),
+        ]);
+        assert!(subnets.is_empty());
+    }
+
+    #[test]
+    fn point_to_point_and_single_host_interfaces_are_skipped() {
+        let subnets = subnets(vec![
+            // vpn tunnel
+            interface("10.8.0.6", "255.255.255.0", None),
+            interface("100.64.3.7", "255.255.255.255", Some("100.64.3.7")),
+            interface("192.168.7.0", "255.255.255.254", Some("192.168.7.1")),
+        ]);
+        assert!(subnets.is_empty());
+    }
+
+    #[test]
+    fn ipv6_is_skipped() {
+        let subnets = subnets(vec![Interface {
+            name: "eth0".to_owned(),
+            addr: IfAddr::V6(if_addrs::Ifv6Addr {
+                ip: "fe80::1".parse().unwrap(),
+                netmask: "ffff:ffff:ffff:ffff::".parse().unwrap(),
+                broadcast: None,
+            }),
+        }]);
+        assert!(subnets.is_empty());
+    }
+
+    const CONFIG: &str = r#"{
+        "name": "Test bridge",
+        "datastoreversion": "131",
+        "swversion": "1953188020",
+        "apiversion": "1.56.0",
+        "mac": "00:17:88:6c:2a:3b",
+        "bridgeid": "001788FFFE6C2A3B",
+        "factorynew": false,
+        "modelid": "BSB002",
+        "starterkitid": ""
+    }"#;
+
+    /// Answers with the bridge configuration (or nothing) after `delay`, counting the requests.
+    #[derive(Debug)]
+    struct Bridge {
+        status: StatusCode,
+        delay: Duration,
+        requests: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Transport for Bridge {
+        async fn execute(&self, _: Request) -> Result<Response, Error> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            let body = match self.status {
+                StatusCode::OK => CONFIG,
+                _ => "",
+            };
+            Ok(Response::new(self.status, body))
+        }
+    }
+
+    fn session(status: StatusCode, delay: Duration) -> (Session, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let transport = Bridge {
+            status,
+            delay,
+            requests: requests.clone(),
+        };
+        (
+            Session::builder().transport(transport).build().unwrap(),
+            requests,
+        )
+    }
+
+    fn manual() -> DiscoveryBroker<Manual> {
+        let (session, _) = session(StatusCode::OK, Duration::ZERO);
+        DiscoveryBroker::from(
+            Manual::new()
+                .with_ip(Ipv4Addr::new(192, 168, 1, 2))
+                .with_session(session),
+        )
+    }
+
+    #[tokio::test]
+    async fn verify_through_own_session() {
+        let (session, requests) = session(StatusCode::OK, Duration::ZERO);
+        let bridges = manual()
+            .verified()
+            .with_session(session)
+            .discover()
+            .await
+            .unwrap();
+        assert_eq!(bridges.0.len(), 1);
+        assert_eq!(bridges.0[0].id(), Some("001788fffe6c2a3b"));
+        assert!(bridges.0[0].config().is_some());
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn verify_without_retries() {
+        // retried by the default policy of the session
+        let (session, requests) = session(StatusCode::SERVICE_UNAVAILABLE, Duration::ZERO);
+        let bridges = manual()
+            .verified()
+            .with_session(session)
+            .discover()
+            .await
+            .unwrap();
+        assert!(bridges.0.is_empty());
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn verify_times_out_own_session() {
+        let (session, _) = session(StatusCode::OK, Duration::from_secs(10));
+        let started = std::time::Instant::now();
+        let bridges = manual()
+            .verified()
+            .with_timeout(Duration::from_millis(50))
+            .with_session(session)
+            .discover()
+            .await
+            .unwrap();
+        assert!(bridges.0.is_empty());
+        assert!(started.elapsed() < Duration::from_secs(5));
     }
 }
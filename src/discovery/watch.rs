@@ -0,0 +1,367 @@
+use super::{BoxDiscoverer, Discoverer, DiscoveryBroker, MDNS_SERVICE};
+use crate::error::Error;
+use crate::resources::{UnauthBridge, UnauthBridges};
+use futures::stream::BoxStream;
+use futures_util::{stream, stream::StreamExt, Stream};
+use std::collections::HashMap;
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::time::{Duration, Instant};
+
+/// Change on the network noticed by a [`DiscoveryWatch`].
+#[derive(Debug, Clone)]
+pub enum DiscoveryEvent {
+    BridgeAppeared(UnauthBridge),
+    /// The bridge hasn't been seen for longer than the [ttl](DiscoveryWatch::with_ttl).
+    BridgeDisappeared(UnauthBridge),
+    BridgeAddressChanged {
+        bridge: UnauthBridge,
+        old: Ipv4Addr,
+    },
+}
+
+/// Keeps discovering bridges and yields a [`DiscoveryEvent`] whenever one appears, disappears
+/// or moves to another address.
+///
+/// Listens on mDNS for as long as the stream is polled, querying the network every 15 seconds
+/// by default. Other protocols can be added to periodically rescan the network, mind the
+/// request limit of the [discovery endpoint](super::DiscoveryEndpoint) when you do.
+///
+/// ```no_run
+/// use futures::StreamExt;
+/// use lightrary::discovery::{DiscoveryBroker, DiscoveryEvent, DiscoveryWatch};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let watch = DiscoveryWatch::new()
+///     .with_ttl(Duration::from_secs(120))
+///     .with_rescan(DiscoveryBroker::ssdp(), Duration::from_secs(300));
+/// let mut events = Box::pin(watch.watch()?);
+/// while let Some(event) = events.next().await {
+///     match event? {
+///         DiscoveryEvent::BridgeAppeared(bridge) => println!("plugged in: {:?}", bridge.id()),
+///         DiscoveryEvent::BridgeDisappeared(bridge) => println!("unplugged: {:?}", bridge.id()),
+///         DiscoveryEvent::BridgeAddressChanged { bridge, old } => {
+///             println!("moved from {} to {}", old, bridge.ip())
+///         }
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct DiscoveryWatch {
+    query_interval: Duration,
+    ttl: Duration,
+    rescans: Vec<(BoxDiscoverer, Duration)>,
+}
+
+impl fmt::Debug for DiscoveryWatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiscoveryWatch")
+            .field("query_interval", &self.query_interval)
+            .field("ttl", &self.ttl)
+            .field("rescans", &self.rescans.len())
+            .finish()
+    }
+}
+
+/// Queries mDNS every 15 seconds, bridges disappear after not being seen for a minute.
+impl Default for DiscoveryWatch {
+    fn default() -> Self {
+        Self {
+            query_interval: Duration::from_secs(15),
+            ttl: Duration::from_secs(60),
+            rescans: Vec::new(),
+        }
+    }
+}
+
+impl DiscoveryWatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How often the network is queried over mDNS.
+    pub fn with_query_interval(mut self, interval: Duration) -> Self {
+        self.query_interval = interval;
+        self
+    }
+
+    /// How long a bridge can go unseen before it's considered gone, keep this a couple of
+    /// times longer than the query and rescan intervals.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Also discovers bridges with `broker` right away and then every `interval`.
+    pub fn with_rescan<D>(mut self, broker: DiscoveryBroker<D>, interval: Duration) -> Self
+    where
+        D: Discoverer + Send + Sync + 'static,
+        D::Device: Into<UnauthBridges>,
+    {
+        self.rescans.push((broker.boxed(), interval));
+        self
+    }
+
+    /// Starts watching, the stream never ends. Errors of a single query or rescan are yielded
+    /// without stopping the stream.
+    pub fn watch(
+        &self,
+    ) -> Result<impl Stream<Item = Result<DiscoveryEvent, Error>> + Send + 'static, Error> {
+        let mdns = mdns::discover::all(MDNS_SERVICE, self.query_interval)?
+            .listen()
+            .map(|res| Ok(Signal::Seen(mdns_bridge(&res?).into_iter().collect())))
+            .boxed();
+
+        // checking a couple of times per ttl keeps bridges from lingering for too long
+        let ttl = self.ttl;
+        let expire = stream::unfold((), move |_| async move {
+            tokio::time::sleep(ttl / 4).await;
+            Some((Ok(Signal::Expire), ()))
+        })
+        .boxed();
+
+        let rescans = self.rescans.iter().cloned().map(|(discover, interval)| {
+            stream::unfold(true, move |first| {
+                let discover = discover.clone();
+                async move {
+                    if !first {
+                        tokio::time::sleep(interval).await;
+                    }
+                    Some((
+                        discover().await.map(|bridges| Signal::Seen(bridges.0)),
+                        false,
+                    ))
+                }
+            })
+            .boxed()
+        });
+
+        let mut known = Known::default();
+        let signals: Vec<BoxStream<'static, Result<Signal, Error>>> =
+            [mdns, expire].into_iter().chain(rescans).collect();
+        Ok(stream::select_all(signals).flat_map(move |signal| {
+            let events = match signal {
+                Ok(Signal::Seen(bridges)) => known
+                    .seen(bridges, Instant::now())
+                    .into_iter()
+                    .map(Ok)
+                    .collect(),
+                Ok(Signal::Expire) => known
+                    .expire(ttl, Instant::now())
+                    .into_iter()
+                    .map(Ok)
+                    .collect(),
+                Err(e) => vec![Err(e)],
+            };
+            stream::iter(events)
+        }))
+    }
+}
+
+enum Signal {
+    Seen(Vec<UnauthBridge>),
+    Expire,
+}
+
+// bridges advertise their id in a `bridgeid=001788fffe6c2a3b` txt record
+fn mdns_bridge(response: &mdns::Response) -> Option<UnauthBridge> {
+    let ip = response.records().find_map(|record| match record.kind {
+        mdns::RecordKind::A(ip) => Some(ip),
+        _ => None,
+    })?;
+    let id = response
+        .txt_records()
+        .find_map(|txt| txt.strip_prefix("bridgeid="))
+        .map(str::to_lowercase);
+    tracing::trace!(%ip, ?id, "mdns response");
+
+    Some(UnauthBridge {
+        id,
+        ip,
+        port: response.port().unwrap_or(443),
+        config: None,
+    })
+}
+
+/// Bridges seen so far keyed by their id, or their ip when the protocol doesn't tell the id.
+#[derive(Default)]
+struct Known(HashMap<String, (UnauthBridge, Instant)>);
+
+impl Known {
+    fn seen(&mut self, bridges: Vec<UnauthBridge>, now: Instant) -> Vec<DiscoveryEvent> {
+        let mut events = Vec::new();
+        for bridge in bridges {
+            let key = self.key(&bridge);
+            match self.0.get_mut(&key) {
+                Some((known, last_seen)) => {
+                    *last_seen = now;
+                    if known.id.is_none() {
+                        known.id = bridge.id;
+                    }
+                    if known.ip != bridge.ip {
+                        let old = known.ip;
+                        known.ip = bridge.ip;
+                        known.port = bridge.port;
+                        events.push(DiscoveryEvent::BridgeAddressChanged {
+                            bridge: known.clone(),
+                            old,
+                        });
+                    }
+                }
+                None => {
+                    events.push(DiscoveryEvent::BridgeAppeared(bridge.clone()));
+                    self.0.insert(key, (bridge, now));
+                }
+            }
+        }
+        events
+    }
+
+    fn key(&mut self, bridge: &UnauthBridge) -> String {
+        let by_ip = bridge.ip.to_string();
+        let id = match &bridge.id {
+            Some(id) => id.to_lowercase(),
+            // only the address to go by, which might belong to a bridge seen with its id
+            None => {
+                return self
+                    .0
+                    .iter()
+                    .find(|(_, (known, _))| known.ip == bridge.ip)
+                    .map_or(by_ip, |(key, _)| key.clone())
+            }
+        };
+        // first seen without its id, from now on it's known by its id
+        if !self.0.contains_key(&id) {
+            if let Some(known) = self.0.remove(&by_ip) {
+                self.0.insert(id.clone(), known);
+            }
+        }
+        id
+    }
+
+    fn expire(&mut self, ttl: Duration, now: Instant) -> Vec<DiscoveryEvent> {
+        let mut events = Vec::new();
+        self.0.retain(|_, (bridge, last_seen)| {
+            let alive = now.duration_since(*last_seen) < ttl;
+            if !alive {
+                events.push(DiscoveryEvent::BridgeDisappeared(bridge.clone()));
+            }
+            alive
+        });
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bridge(id: Option<&str>, ip: [u8; 4]) -> UnauthBridge {
+        UnauthBridge {
+            id: id.map(str::to_owned),
+            ip: Ipv4Addr::from(ip),
+            port: 443,
+            config: None,
+        }
+    }
+
+    #[test]
+    fn appear_once() {
+        let now = Instant::now();
+        let mut known = Known::default();
+
+        let events = known.seen(vec![bridge(Some("001788fffe6c2a3b"), [10, 0, 0, 2])], now);
+        assert!(matches!(
+            &events[..],
+            [DiscoveryEvent::BridgeAppeared(b)] if b.id() == Some("001788fffe6c2a3b")
+        ));
+        // the same id in another case is still the same bridge
+        let events = known.seen(vec![bridge(Some("001788FFFE6C2A3B"), [10, 0, 0, 2])], now);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn address_change() {
+        let now = Instant::now();
+        let mut known = Known::default();
+
+        known.seen(vec![bridge(Some("001788fffe6c2a3b"), [10, 0, 0, 2])], now);
+        let events = known.seen(vec![bridge(Some("001788fffe6c2a3b"), [10, 0, 0, 7])], now);
+        assert!(matches!(
+            &events[..],
+            [DiscoveryEvent::BridgeAddressChanged { bridge, old }]
+                if bridge.ip() == Ipv4Addr::new(10, 0, 0, 7) && *old == Ipv4Addr::new(10, 0, 0, 2)
+        ));
+    }
+
+    #[test]
+    fn rekeyed_from_ip_to_id() {
+        let now = Instant::now();
+        let mut known = Known::default();
+
+        // e.g. found by a network scan first and by mdns after
+        let events = known.seen(vec![bridge(None, [10, 0, 0, 2])], now);
+        assert!(matches!(&events[..], [DiscoveryEvent::BridgeAppeared(b)] if b.id().is_none()));
+        let events = known.seen(vec![bridge(Some("001788fffe6c2a3b"), [10, 0, 0, 2])], now);
+        assert!(events.is_empty());
+        assert_eq!(known.0.len(), 1);
+        assert_eq!(known.0["001788fffe6c2a3b"].0.id(), Some("001788fffe6c2a3b"));
+
+        // seen without its id again, it's the bridge known by its id at that address
+        let events = known.seen(vec![bridge(None, [10, 0, 0, 2])], now);
+        assert!(events.is_empty());
+        assert_eq!(known.0.len(), 1);
+
+        let events = known.seen(vec![bridge(Some("001788fffe6c2a3b"), [10, 0, 0, 7])], now);
+        assert!(matches!(
+            &events[..],
+            [DiscoveryEvent::BridgeAddressChanged { old, .. }] if *old == Ipv4Addr::new(10, 0, 0, 2)
+        ));
+    }
+
+    #[test]
+    fn expire_after_ttl() {
+        let start = Instant::now();
+        let ttl = Duration::from_secs(60);
+        let mut known = Known::default();
+
+        known.seen(vec![bridge(Some("001788fffe6c2a3b"), [10, 0, 0, 2])], start);
+        known.seen(
+            vec![bridge(Some("001788fffe000001"), [10, 0, 0, 3])],
+            start + Duration::from_secs(30),
+        );
+
+        assert!(known
+            .expire(ttl, start + Duration::from_secs(59))
+            .is_empty());
+        let events = known.expire(ttl, start + Duration::from_secs(61));
+        assert!(matches!(
+            &events[..],
+            [DiscoveryEvent::BridgeDisappeared(b)] if b.id() == Some("001788fffe6c2a3b")
+        ));
+        assert!(known
+            .expire(ttl, start + Duration::from_secs(62))
+            .is_empty());
+
+        // seeing a bridge again keeps it alive
+        known.seen(
+            vec![bridge(Some("001788fffe000001"), [10, 0, 0, 3])],
+            start + Duration::from_secs(80),
+        );
+        assert!(known
+            .expire(ttl, start + Duration::from_secs(120))
+            .is_empty());
+        assert_eq!(known.expire(ttl, start + Duration::from_secs(141)).len(), 1);
+
+        // and a bridge that comes back appears again
+        let events = known.seen(
+            vec![bridge(Some("001788fffe6c2a3b"), [10, 0, 0, 2])],
+            start + Duration::from_secs(150),
+        );
+        assert!(matches!(&events[..], [DiscoveryEvent::BridgeAppeared(_)]));
+    }
+}
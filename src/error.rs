@@ -1,5 +1,8 @@
-use crate::resources::{Bridge, Bridges, UnauthBridge};
+use crate::resources::{Bridge, Bridges, Capability, UnauthBridge};
+use crate::session::redacted_path;
+use crate::transport::StatusCode;
 use serde::Deserialize;
+use std::fmt;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -7,11 +10,63 @@ pub enum Error {
     #[error(transparent)]
     Reqwest(#[from] reqwest::Error),
     #[error(transparent)]
+    #[cfg(feature = "mdns")]
     Mdns(#[from] mdns::Error),
     #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
     AddrParse(#[from] std::net::AddrParseError),
     #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// Failure of a custom [`Transport`](crate::transport::Transport).
+    #[error(transparent)]
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+    /// Response with a `4xx` or `5xx` status where a successful one was expected.
+    #[error("request failed with status {0}")]
+    Status(StatusCode),
+    #[error(transparent)]
     GenKey(#[from] GenKeyError),
+    #[error(transparent)]
+    Api(#[from] ApiError),
+    #[error(transparent)]
+    Clip(#[from] ClipError),
+    #[error("bridge has no application key, use `Bridge::gen_key` or `Bridge::with_key` first")]
+    MissingAppKey,
+    #[error("unexpected response from bridge: {0}")]
+    UnexpectedResponse(String),
+    #[error("the remote api needs a session that verifies certificates, build it with `accept_invalid_certs(false)`")]
+    InsecureSession,
+    #[error("bridge is reached through the remote api, restore it with `BridgeDescriptor::into_remote_bridge`")]
+    RemoteDescriptor,
+    #[error("can't search for {0} serial numbers at once, the bridge takes up to 10")]
+    TooManySerials(usize),
+    #[error("backup version {0} is not supported by this version of lightrary")]
+    UnsupportedBackupVersion(u32),
+    #[error("invalid url: {0}")]
+    InvalidUrl(String),
+    #[error("bridge doesn't support {capability}, its api version is {api_version}")]
+    Unsupported {
+        capability: Capability,
+        api_version: String,
+    },
+}
+
+/// Displays an error for logging, with the app key left out of the url of a failed request.
+pub(crate) struct Redacted<'a>(pub(crate) &'a Error);
+
+impl fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Error::Reqwest(e) => match e.url() {
+                Some(url) => {
+                    let redacted = url.as_str().replacen(url.path(), &redacted_path(url), 1);
+                    f.write_str(&e.to_string().replace(url.as_str(), &redacted))
+                }
+                None => e.fmt(f),
+            },
+            e => e.fmt(f),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -49,7 +104,9 @@ pub enum GenKeyResult {
 #[derive(Debug, Deserialize)]
 pub struct GenKeySuccess {
     pub(crate) username: String,
-    pub(crate) clientkey: String,
+    // not handed out through the remote api
+    #[serde(default)]
+    pub(crate) clientkey: Option<String>,
 }
 
 #[derive(Error, Debug, Clone, Deserialize)]
@@ -60,3 +117,29 @@ pub struct GenKeyError {
     pub address: String,
     pub description: String,
 }
+
+/// Result entry as returned by the v1 API for every write (and some read) requests.
+#[derive(Debug, Deserialize)]
+pub(crate) enum ApiResult<T> {
+    #[serde(rename = "success")]
+    Success(T),
+    #[serde(rename = "error")]
+    Error(ApiError),
+}
+
+/// Error as reported by the bridge itself through the v1 API.
+#[derive(Error, Debug, Clone, Deserialize)]
+#[error("{description} ({address})")]
+pub struct ApiError {
+    #[serde(rename = "type")]
+    pub t: i32,
+    pub address: String,
+    pub description: String,
+}
+
+/// Error(s) as reported by the bridge through the CLIP v2 API.
+#[derive(Error, Debug, Clone)]
+#[error("{}", .descriptions.join(", "))]
+pub struct ClipError {
+    pub descriptions: Vec<String>,
+}
@@ -1,6 +1,17 @@
 #![warn(rust_2018_idioms)]
 
+#[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+compile_error!("bridges only speak https, enable either the `native-tls` or `rustls` feature");
+
+pub mod backup;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod discovery;
 pub mod error;
+pub mod remote;
 pub mod resources;
-mod session;
+pub mod retry;
+pub mod session;
+#[cfg(feature = "metrics")]
+pub mod telemetry;
+pub mod transport;
@@ -10,17 +10,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // }).await?;
 
     let broker = DiscoveryBroker::manual("192.168.50.173".parse()?);
-    let bridge = broker.discover().await?;
-    let b = match bridge.auth().await {
-        Ok(bridge) => bridge,
-        Err((unauth_bridge, err)) => {
+    let bridges = broker.discover().await?;
+    let (bridges, _failed) = bridges.auth().await.into();
+    let b = match bridges.into_singular() {
+        Some(bridge) => bridge,
+        None => {
             // retry
             todo!()
         }
     };
-    // let (bridges, _failed) = bridges.auth().await.into();
-    // let bridge = bridges.into_singular();
-    let (bridge, app_key) = b.gen_key("test_app", "yeey").await?;
+    let (_bridge, app_key) = b.gen_key("test_app", "yeey").await?;
     println!("{:?}", app_key);
 
 
@@ -0,0 +1,359 @@
+//! Controlling bridges outside of your local network through the Hue Remote API.
+//!
+//! Requests are routed through `https://api.meethue.com/route` and authorized with an OAuth2
+//! access token. Register your app at <https://developers.meethue.com> to get a client id and
+//! secret, send the user to the [authorize url](RemoteApp::authorize_url) and exchange the
+//! code Hue redirects back with for tokens. The [`Bridge`] you get from there has the same
+//! resource api as a local one.
+//!
+//! ```no_run
+//! use lightrary::remote::RemoteApp;
+//! use lightrary::session::Session;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! // unlike bridges the remote api has a proper certificate, credentials are only sent
+//! // through a session that verifies it
+//! let session = Session::builder().accept_invalid_certs(false).build()?;
+//! let app = RemoteApp::new("client-id", "client-secret");
+//! println!("log in at {}", app.authorize_url("some-random-state")?);
+//! # let code = "";
+//! // ...the user logs in and Hue redirects back with a code
+//! let remote = app
+//!     .authorize(&session, code)
+//!     .await?
+//!     .on_refresh(|tokens| println!("persist these: {:?}", tokens));
+//! let (bridge, app_key) = remote.bridge().await?.gen_key("my_app", "remote").await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The base url is configurable with [`RemoteApp::with_base_url`] so the whole flow can be
+//! tested against a local stand-in server, see [`SessionBuilder::https_only`](crate::session::SessionBuilder::https_only).
+
+use crate::error::Error;
+use crate::resources::{Bridge, BridgeConfig};
+use crate::session::Session;
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+const DEFAULT_BASE_URL: &str = "https://api.meethue.com";
+// refresh a little before the token expires so requests in flight don't get rejected
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// Your app as registered with Hue, the starting point of the OAuth2 flow.
+#[derive(Clone)]
+pub struct RemoteApp {
+    client_id: String,
+    client_secret: String,
+    base_url: String,
+}
+
+impl fmt::Debug for RemoteApp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteApp")
+            .field("client_id", &self.client_id)
+            .field("base_url", &self.base_url)
+            .finish()
+    }
+}
+
+impl RemoteApp {
+    pub fn new(client_id: &str, client_secret: &str) -> Self {
+        Self {
+            client_id: client_id.to_owned(),
+            client_secret: client_secret.to_owned(),
+            base_url: DEFAULT_BASE_URL.to_owned(),
+        }
+    }
+
+    /// Replaces `https://api.meethue.com`, e.g. with `http://127.0.0.1:8080` to test against a
+    /// local stand-in server.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Url to send the user to for logging in and granting your app access. Hue redirects back
+    /// to the callback url you registered with a `code` and the `state` passed here, check the
+    /// state to make sure the redirect belongs to a login you started.
+    pub fn authorize_url(&self, state: &str) -> Result<Url, Error> {
+        let url = format!("{}/v2/oauth2/authorize", self.base_url);
+        Url::parse_with_params(
+            &url,
+            &[
+                ("client_id", self.client_id.as_str()),
+                ("response_type", "code"),
+                ("state", state),
+            ],
+        )
+        .map_err(|_| Error::InvalidUrl(url))
+    }
+
+    /// Exchanges the `code` Hue redirected back with for tokens.
+    ///
+    /// Fails with [`Error::InsecureSession`] when `session` accepts invalid certificates.
+    pub async fn authorize(&self, session: &Session, code: &str) -> Result<Remote, Error> {
+        require_verified(session)?;
+        let tokens = self
+            .request_tokens(
+                session,
+                &[("grant_type", "authorization_code"), ("code", code)],
+            )
+            .await?;
+        Remote::from_tokens(self.clone(), session, tokens)
+    }
+
+    async fn request_tokens(
+        &self,
+        session: &Session,
+        form: &[(&str, &str)],
+    ) -> Result<Tokens, Error> {
+        #[derive(Deserialize)]
+        struct TokenResponse {
+            access_token: String,
+            refresh_token: String,
+            expires_in: u64,
+        }
+
+        let req = session
+            .request(Method::POST, format!("{}/v2/oauth2/token", self.base_url))
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .form(form);
+        let res = session
+            .send(req)
+            .await?
+            .error_for_status()?
+            .json::<TokenResponse>()
+            .await?;
+
+        Ok(Tokens {
+            access_token: res.access_token,
+            refresh_token: res.refresh_token,
+            expires_at: unix_now() + res.expires_in,
+        })
+    }
+}
+
+/// OAuth2 tokens of a logged in user, persist them to skip logging in again next time.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Tokens {
+    access_token: String,
+    refresh_token: String,
+    // seconds since the unix epoch
+    expires_at: u64,
+}
+
+impl fmt::Debug for Tokens {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tokens")
+            .field("access_token", &"<redacted>")
+            .field("refresh_token", &"<redacted>")
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+impl Tokens {
+    pub fn access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    pub fn refresh_token(&self) -> &str {
+        &self.refresh_token
+    }
+
+    /// When the access token expires, it's refreshed automatically shortly before.
+    pub fn expires_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.expires_at)
+    }
+
+    fn expires_soon(&self) -> bool {
+        unix_now() + REFRESH_MARGIN.as_secs() >= self.expires_at
+    }
+}
+
+type RefreshHook = Arc<dyn Fn(&Tokens) + Send + Sync>;
+
+/// Access to a user's bridge through the remote api, refreshing the tokens when needed.
+///
+/// Clones share their tokens.
+#[derive(Clone)]
+pub struct Remote {
+    app: RemoteApp,
+    session: Session,
+    tokens: Arc<Mutex<Tokens>>,
+    on_refresh: Option<RefreshHook>,
+}
+
+impl fmt::Debug for Remote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Remote")
+            .field("app", &self.app)
+            .field("on_refresh", &self.on_refresh.is_some())
+            .finish()
+    }
+}
+
+impl Remote {
+    /// Picks up where an earlier login left off with the tokens you persisted.
+    ///
+    /// Fails with [`Error::InsecureSession`] when `session` accepts invalid certificates.
+    pub fn from_tokens(app: RemoteApp, session: &Session, tokens: Tokens) -> Result<Self, Error> {
+        require_verified(session)?;
+        Ok(Self {
+            app,
+            session: session.clone(),
+            tokens: Arc::new(Mutex::new(tokens)),
+            on_refresh: None,
+        })
+    }
+
+    /// Called with the new tokens after every refresh. Persist them, the refresh token changes
+    /// with every refresh too.
+    pub fn on_refresh<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Tokens) + Send + Sync + 'static,
+    {
+        self.on_refresh = Some(Arc::new(f));
+        self
+    }
+
+    pub fn app(&self) -> &RemoteApp {
+        &self.app
+    }
+
+    /// The current tokens.
+    pub async fn tokens(&self) -> Tokens {
+        self.tokens.lock().await.clone()
+    }
+
+    /// Refreshes the tokens right away instead of shortly before they expire.
+    pub async fn refresh(&self) -> Result<(), Error> {
+        let mut tokens = self.tokens.lock().await;
+        self.refresh_tokens(&mut tokens).await
+    }
+
+    /// The bridge of the logged in user. It still needs an app key, generate one with
+    /// [`Bridge::gen_key`] (no need to press the link button) or reuse one with [`Bridge::with_key`].
+    pub async fn bridge(&self) -> Result<Bridge, Error> {
+        let req = self
+            .session
+            .get(format!("{}/route/api/0/config", self.base_url()))
+            .bearer_auth(self.access_token().await?);
+        let config = self
+            .session
+            .send(req)
+            .await?
+            .error_for_status()?
+            .json::<BridgeConfig>()
+            .await?;
+
+        Ok(Bridge {
+            id: Some(config.bridge_id().to_lowercase()),
+            ip: Arc::new(RwLock::new(Ipv4Addr::UNSPECIFIED)),
+            port: 443,
+            app_key: None,
+            client_key: None,
+            session: self.session.clone(),
+            watchdog: None,
+            remote: Some(self.clone()),
+            config,
+        })
+    }
+
+    pub(crate) fn session(&self) -> &Session {
+        &self.session
+    }
+
+    pub(crate) fn base_url(&self) -> &str {
+        &self.app.base_url
+    }
+
+    pub(crate) async fn access_token(&self) -> Result<String, Error> {
+        // held during the refresh so concurrent requests wait for it instead of all refreshing
+        let mut tokens = self.tokens.lock().await;
+        if tokens.expires_soon() {
+            self.refresh_tokens(&mut tokens).await?;
+        }
+        Ok(tokens.access_token.clone())
+    }
+
+    async fn refresh_tokens(&self, tokens: &mut Tokens) -> Result<(), Error> {
+        *tokens = self
+            .app
+            .request_tokens(
+                &self.session,
+                &[
+                    ("grant_type", "refresh_token"),
+                    ("refresh_token", &tokens.refresh_token),
+                ],
+            )
+            .await?;
+        if let Some(on_refresh) = &self.on_refresh {
+            on_refresh(tokens);
+        }
+        Ok(())
+    }
+}
+
+// the client secret, codes and tokens must not end up at whoever intercepts the connection
+fn require_verified(session: &Session) -> Result<(), Error> {
+    match session.verifies_certs() {
+        true => Ok(()),
+        false => Err(Error::InsecureSession),
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens() -> Tokens {
+        Tokens {
+            access_token: "access".into(),
+            refresh_token: "refresh".into(),
+            expires_at: u64::MAX,
+        }
+    }
+
+    #[test]
+    fn refuse_unverified_session() {
+        let app = RemoteApp::new("id", "secret");
+        let session = Session::new().unwrap();
+        assert!(matches!(
+            Remote::from_tokens(app.clone(), &session, tokens()),
+            Err(Error::InsecureSession)
+        ));
+
+        let session = Session::builder()
+            .accept_invalid_certs(false)
+            .build()
+            .unwrap();
+        assert!(Remote::from_tokens(app, &session, tokens()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn authorize_refuses_before_sending_the_code() {
+        // the base url can't be reached, an attempt to send would fail differently
+        let app = RemoteApp::new("id", "secret").with_base_url("https://127.0.0.1:9");
+        let res = app.authorize(&Session::new().unwrap(), "code").await;
+        assert!(matches!(res, Err(Error::InsecureSession)));
+    }
+}
@@ -0,0 +1,275 @@
+use crate::error::Error;
+use crate::resources::{Bridge, ResourceIdentifier};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// Automation the bridge can run on its own, e.g. wake up, go to sleep, timers, coming home
+/// and natural light (CLIP v2 `behavior_script`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct BehaviorScript {
+    id: String,
+    description: String,
+    configuration_schema: serde_json::Value,
+    version: String,
+    metadata: BehaviorScriptMetadata,
+    #[serde(default)]
+    max_number_instances: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct BehaviorScriptMetadata {
+    name: String,
+    category: BehaviorCategory,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BehaviorCategory {
+    Automation,
+    Entertainment,
+    Accessory,
+    #[serde(other)]
+    Other,
+}
+
+impl BehaviorScript {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.metadata.name
+    }
+
+    pub fn category(&self) -> BehaviorCategory {
+        self.metadata.category
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// JSON schema the configuration of an instance of this script has to adhere to.
+    pub fn configuration_schema(&self) -> &serde_json::Value {
+        &self.configuration_schema
+    }
+
+    pub fn max_number_instances(&self) -> Option<u32> {
+        self.max_number_instances
+    }
+}
+
+/// A configured automation running on the bridge (CLIP v2 `behavior_instance`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct BehaviorInstance {
+    id: String,
+    script_id: String,
+    enabled: bool,
+    configuration: serde_json::Value,
+    #[serde(default)]
+    dependees: Vec<BehaviorDependee>,
+    status: BehaviorStatus,
+    #[serde(default)]
+    last_error: Option<String>,
+    metadata: BehaviorInstanceMetadata,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BehaviorInstanceMetadata {
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct BehaviorDependee {
+    target: ResourceIdentifier,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BehaviorStatus {
+    Initializing,
+    Running,
+    Disabled,
+    Errored,
+    #[serde(other)]
+    Unknown,
+}
+
+impl BehaviorInstance {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.metadata.name
+    }
+
+    /// Id of the [`BehaviorScript`] this is an instance of.
+    pub fn script_id(&self) -> &str {
+        &self.script_id
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn configuration(&self) -> &serde_json::Value {
+        &self.configuration
+    }
+
+    /// Resources (rooms, lights, sensors, ...) this instance acts upon.
+    pub fn dependees(&self) -> impl Iterator<Item = &ResourceIdentifier> {
+        self.dependees.iter().map(|dependee| &dependee.target)
+    }
+
+    pub fn status(&self) -> BehaviorStatus {
+        self.status
+    }
+
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref().filter(|e| !e.is_empty())
+    }
+}
+
+/// New instance of a [`BehaviorScript`], the configuration has to adhere to the script's
+/// [`configuration_schema`](BehaviorScript::configuration_schema).
+///
+/// ```no_run
+/// use lightrary::resources::{Bridge, NewBehaviorInstance};
+///
+/// # async fn run(bridge: Bridge) -> Result<(), lightrary::error::Error> {
+/// let script = bridge.behavior_script_named("Timers").await?.unwrap();
+/// let config = serde_json::json!({
+///     "duration": { "seconds": 600 },
+///     "where": [{ "group": { "rid": "6ddc9066-7e7d-4a68-a179-ad3e5ce1b3b5", "rtype": "room" } }],
+/// });
+/// let instance = NewBehaviorInstance::new(&script, "Tea timer").with_configuration(config);
+/// let id = bridge.create_behavior_instance(&instance).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct NewBehaviorInstance {
+    #[serde(rename = "type")]
+    t: &'static str,
+    script_id: String,
+    enabled: bool,
+    configuration: serde_json::Value,
+    metadata: BehaviorInstanceMetadata,
+}
+
+impl NewBehaviorInstance {
+    pub fn new(script: &BehaviorScript, name: &str) -> Self {
+        Self {
+            t: "behavior_instance",
+            script_id: script.id.clone(),
+            enabled: true,
+            configuration: serde_json::Value::Object(Default::default()),
+            metadata: BehaviorInstanceMetadata {
+                name: name.to_owned(),
+            },
+        }
+    }
+
+    pub fn with_configuration(mut self, configuration: serde_json::Value) -> Self {
+        self.configuration = configuration;
+        self
+    }
+
+    /// Instances are enabled by default.
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+struct BehaviorInstanceChange<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    configuration: Option<&'a serde_json::Value>,
+}
+
+impl Bridge {
+    pub async fn behavior_scripts(&self) -> Result<Vec<BehaviorScript>, Error> {
+        self.v2_get("behavior_script").await
+    }
+
+    /// Finds a script by its name, e.g. `Wake up`, `Go to sleep`, `Timers`, `Coming home`
+    /// or `Natural light`.
+    pub async fn behavior_script_named(&self, name: &str) -> Result<Option<BehaviorScript>, Error> {
+        Ok(self
+            .behavior_scripts()
+            .await?
+            .into_iter()
+            .find(|script| script.name() == name))
+    }
+
+    pub async fn behavior_instances(&self) -> Result<Vec<BehaviorInstance>, Error> {
+        self.v2_get("behavior_instance").await
+    }
+
+    /// Creates a new instance and returns its id.
+    pub async fn create_behavior_instance(
+        &self,
+        instance: &NewBehaviorInstance,
+    ) -> Result<String, Error> {
+        self.v2_send(Method::POST, "/behavior_instance", Some(instance))
+            .await?
+            .into_iter()
+            .next()
+            .map(|created| created.rid)
+            .ok_or_else(|| Error::UnexpectedResponse("missing id of created resource".into()))
+    }
+
+    pub async fn set_behavior_instance_enabled(
+        &self,
+        id: &str,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        self.update_behavior_instance(
+            id,
+            &BehaviorInstanceChange {
+                enabled: Some(enabled),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    pub async fn set_behavior_instance_configuration(
+        &self,
+        id: &str,
+        configuration: &serde_json::Value,
+    ) -> Result<(), Error> {
+        self.update_behavior_instance(
+            id,
+            &BehaviorInstanceChange {
+                configuration: Some(configuration),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    async fn update_behavior_instance(
+        &self,
+        id: &str,
+        change: &BehaviorInstanceChange<'_>,
+    ) -> Result<(), Error> {
+        let path = format!("/behavior_instance/{}", id);
+        self.v2_send(Method::PUT, &path, Some(change)).await?;
+        Ok(())
+    }
+
+    pub async fn delete_behavior_instance(&self, id: &str) -> Result<(), Error> {
+        let path = format!("/behavior_instance/{}", id);
+        self.v2_send::<()>(Method::DELETE, &path, None).await?;
+        Ok(())
+    }
+}
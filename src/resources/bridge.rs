@@ -1,8 +1,15 @@
-use crate::error::{AuthFailed, AuthResults, Error, GenKeyResult};
-use crate::resources::light::Lights;
+use crate::error::{ApiError, ApiResult, AuthFailed, AuthResults, Error, GenKeyResult, Redacted};
+use crate::remote::Remote;
+use crate::resources::clip::{ClipResponse, ResourceIdentifier};
+use crate::resources::{Capability, Watchdog};
 use crate::session::Session;
+use crate::transport::Response;
+use reqwest::{Method, RequestBuilder};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::net::Ipv4Addr;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::sync::{Arc, RwLock};
 
 // TODO: remove, dynamically get this when authenticating bridge
 // const USER: &'static str = "JGQOy1ADXKSa3uuJNZDv5xcGrD9t-AHgoEXki-6a";
@@ -11,57 +18,54 @@ use std::net::Ipv4Addr;
 pub struct UnauthBridges(pub(crate) Vec<UnauthBridge>);
 
 impl UnauthBridges {
+    /// Authenticates every bridge, sharing a single [`Session`] between them.
     pub async fn auth(self) -> AuthResults {
         let mut results = AuthResults {
             success: Bridges(Vec::new()),
             failed: Vec::new(),
         };
+        let mut session = None;
         for bridge in self {
-            let session = match Session::new() {
-                Ok(session) => session,
-                Err(e) => {
-                    results.failed.push(AuthFailed { bridge, err: e });
-                    continue;
-                }
-            };
-            let res = match session
-                .get(format!("https://{}/api/0/config", bridge.ip))
-                .send()
-                .await
-            {
-                Ok(res) => res,
-                Err(e) => {
-                    results.failed.push(AuthFailed {
-                        bridge,
-                        err: e.into(),
-                    });
-                    continue;
-                }
+            let session = match &session {
+                Some(session) => session,
+                None => match Session::new() {
+                    Ok(new) => session.insert(new),
+                    Err(e) => {
+                        results.failed.push(AuthFailed { bridge, err: e });
+                        continue;
+                    }
+                },
             };
-            let config = match res.json::<BridgeConfig>().await {
-                Ok(config) => config,
-                Err(e) => {
-                    results.failed.push(AuthFailed {
-                        bridge,
-                        err: e.into(),
-                    });
-                    continue;
-                }
-            };
-            results.success.0.push(Bridge {
-                id: bridge.id,
-                ip: bridge.ip,
-                port: bridge.port,
-                app_key: None,
-                client_key: None,
-                session,
-                config,
-            })
+            match bridge.auth_with(session).await {
+                Ok(bridge) => results.success.0.push(bridge),
+                Err((bridge, err)) => results.failed.push(AuthFailed { bridge, err }),
+            }
+        }
+        results
+    }
+
+    /// Authenticates every bridge using (a clone of) your own configured session.
+    pub async fn auth_with(self, session: &Session) -> AuthResults {
+        let mut results = AuthResults {
+            success: Bridges(Vec::new()),
+            failed: Vec::new(),
+        };
+        for bridge in self {
+            match bridge.auth_with(session).await {
+                Ok(bridge) => results.success.0.push(bridge),
+                Err((bridge, err)) => results.failed.push(AuthFailed { bridge, err }),
+            }
         }
         results
     }
 }
 
+impl From<UnauthBridge> for UnauthBridges {
+    fn from(bridge: UnauthBridge) -> Self {
+        Self(vec![bridge])
+    }
+}
+
 impl IntoIterator for UnauthBridges {
     type Item = UnauthBridge;
     type IntoIter = std::vec::IntoIter<Self::Item>;
@@ -77,39 +81,83 @@ pub struct UnauthBridge {
     #[serde(rename = "internalipaddress")]
     pub(crate) ip: Ipv4Addr,
     pub(crate) port: u16,
+    #[serde(skip)]
+    pub(crate) config: Option<BridgeConfig>,
 }
 
 impl UnauthBridge {
+    /// Bridge id, known for every discovery protocol but mDNS (unless the bridges were
+    /// [verified](crate::discovery::DiscoveryBroker::verified)).
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn ip(&self) -> Ipv4Addr {
+        self.ip
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub(crate) fn addr(&self) -> SocketAddrV4 {
+        SocketAddrV4::new(self.ip, self.port)
+    }
+
+    /// Configuration the bridge responded with when it was discovered, only known for
+    /// protocols that contact the bridge and for [verified](crate::discovery::DiscoveryBroker::verified)
+    /// discovery.
+    pub fn config(&self) -> Option<&BridgeConfig> {
+        self.config.as_ref()
+    }
+
     pub async fn auth(self) -> Result<Bridge, (UnauthBridge, Error)> {
         let session = match Session::new() {
             Ok(session) => session,
             Err(e) => return Err((self, e)),
         };
-        let res = match session
-            .get(format!("https://{}/api/0/config", self.ip))
-            .send()
-            .await
-        {
-            Ok(res) => res,
-            Err(e) => return Err((self, e.into())),
-        };
-        let config = match res.json::<BridgeConfig>().await {
+        self.auth_with(&session).await
+    }
+
+    /// Authenticates using (a clone of) your own configured session.
+    pub async fn auth_with(self, session: &Session) -> Result<Bridge, (UnauthBridge, Error)> {
+        let config = match fetch_config(session, self.addr()).await {
             Ok(config) => config,
-            Err(e) => return Err((self, e.into())),
+            Err(e) => {
+                tracing::warn!(addr = %self.addr(), error = %Redacted(&e), "authentication failed");
+                return Err((self, e));
+            }
         };
+        tracing::debug!(
+            addr = %self.addr(),
+            bridge_id = config.bridge_id(),
+            api_version = config.api_version(),
+            "authenticated"
+        );
         Ok(Bridge {
             id: self.id,
-            ip: self.ip,
+            ip: Arc::new(RwLock::new(self.ip)),
             port: self.port,
             app_key: None,
             client_key: None,
-            session,
+            session: session.clone(),
+            watchdog: None,
+            remote: None,
             config,
         })
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// Fetches the public part of the bridge configuration, which doesn't need an app key.
+pub(crate) async fn fetch_config(
+    session: &Session,
+    addr: SocketAddrV4,
+) -> Result<BridgeConfig, Error> {
+    let req = session.get(format!("https://{}/api/0/config", addr));
+    session.send(req).await?.json::<BridgeConfig>().await
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BridgeConfig {
     name: String,
     datastoreversion: String,
@@ -123,47 +171,202 @@ pub struct BridgeConfig {
     starterkitid: Option<String>,
 }
 
+impl BridgeConfig {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn datastore_version(&self) -> &str {
+        &self.datastoreversion
+    }
+
+    pub fn sw_version(&self) -> &str {
+        &self.swversion
+    }
+
+    pub fn api_version(&self) -> &str {
+        &self.apiversion
+    }
+
+    pub fn mac(&self) -> &str {
+        &self.mac
+    }
+
+    pub fn bridge_id(&self) -> &str {
+        &self.bridgeid
+    }
+
+    pub fn factory_new(&self) -> bool {
+        self.factorynew
+    }
+
+    pub fn replaces_bridge_id(&self) -> Option<&str> {
+        self.replacebridgeid.as_deref()
+    }
+
+    pub fn model_id(&self) -> &str {
+        &self.modelid
+    }
+
+    pub fn starterkit_id(&self) -> Option<&str> {
+        self.starterkitid.as_deref()
+    }
+
+    /// Api version as `(major, minor, patch)`, e.g. `(1, 56, 0)`.
+    pub(crate) fn api_version_parts(&self) -> Option<(u32, u32, u32)> {
+        let mut parts = self.apiversion.split('.').map(str::parse);
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch))) => Some((major, minor, patch)),
+            _ => None,
+        }
+    }
+
+    // other devices (or bridge emulators) might answer on the same path, hue bridges
+    // have a `BSB` model id and a 16 digit hex bridge id
+    pub(crate) fn is_hue_bridge(&self) -> bool {
+        self.modelid.starts_with("BSB")
+            && self.bridgeid.len() == 16
+            && self.bridgeid.chars().all(|c| c.is_ascii_hexdigit())
+    }
+}
+
 #[derive(Debug)]
 pub struct Bridges(pub(crate) Vec<Bridge>);
 
 impl Bridges {
-    // concept, might change this
-    // make sure that the user knows if the list is empty panic and if you have more than one panic
-    pub fn into_singular(mut self) -> Bridge {
-        self.0.remove(0)
+    /// Yields the bridge if this list contains exactly one, handy when you know there's only
+    /// a single bridge on your network.
+    pub fn into_singular(mut self) -> Option<Bridge> {
+        match self.0.len() {
+            1 => self.0.pop(),
+            _ => None,
+        }
     }
 }
 
-#[derive(Debug)]
+/// Clones share their address, so the watchdog moving one bridge moves all of them.
+#[derive(Debug, Clone)]
 pub struct Bridge {
     pub(crate) id: Option<String>,
-    pub(crate) ip: Ipv4Addr,
+    // behind a lock so the watchdog can move the bridge to its new address
+    pub(crate) ip: Arc<RwLock<Ipv4Addr>>,
     pub(crate) port: u16,
     pub(crate) app_key: Option<String>,
     pub(crate) client_key: Option<String>,
     pub(crate) session: Session,
+    pub(crate) watchdog: Option<Watchdog>,
+    // set for bridges reached through the remote api instead of the local network
+    pub(crate) remote: Option<Remote>,
     pub(crate) config: BridgeConfig,
 }
 
 impl Bridge {
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Local address of the bridge, `0.0.0.0` for bridges reached through the
+    /// [remote api](crate::remote).
+    pub fn ip(&self) -> Ipv4Addr {
+        *self.ip.read().unwrap()
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub(crate) fn addr(&self) -> SocketAddrV4 {
+        SocketAddrV4::new(self.ip(), self.port)
+    }
+
+    /// Whether this bridge is reached through the [remote api](crate::remote).
+    pub fn is_remote(&self) -> bool {
+        self.remote.is_some()
+    }
+
+    // every url the bridge is reached through starts with this
+    fn base_url(&self) -> String {
+        match &self.remote {
+            Some(remote) => format!("{}/route", remote.base_url()),
+            None => format!("https://{}", self.addr()),
+        }
+    }
+
+    pub fn app_key(&self) -> Option<&str> {
+        self.app_key.as_deref()
+    }
+
+    pub fn client_key(&self) -> Option<&str> {
+        self.client_key.as_deref()
+    }
+
+    pub fn config(&self) -> &BridgeConfig {
+        &self.config
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Swaps the session used to talk to this bridge, e.g. to share one between bridges.
+    pub fn with_session(mut self, session: Session) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Watches for connection failures and follows the bridge to its new address when it
+    /// changed, see [`Watchdog`].
+    pub fn with_watchdog(mut self, watchdog: Watchdog) -> Self {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
     pub fn with_key(mut self, app_key: String) -> Self {
         self.app_key = Some(app_key);
         self
     }
 
+    /// Everything needed to recreate this bridge later on, see [`BridgeDescriptor`].
+    pub fn descriptor(&self) -> BridgeDescriptor {
+        BridgeDescriptor {
+            id: self.id.clone(),
+            ip: self.ip(),
+            port: self.port,
+            app_key: self.app_key.clone(),
+            client_key: self.client_key.clone(),
+            remote: self.is_remote(),
+            config: self.config.clone(),
+        }
+    }
+
     pub async fn gen_key(
         self,
         app_name: &str,
         instance_name: &str,
     ) -> Result<(Self, String), Error> {
+        let device_type = crate::resources::device::DeviceType {
+            app_name,
+            instance_name,
+        };
+        // there's no physical button to press from afar, the remote api has a virtual one
+        if self.is_remote() {
+            let link_button = serde_json::json!({ "linkbutton": true });
+            self.send(|bridge| {
+                Ok(bridge
+                    .session
+                    .request(Method::PUT, format!("{}/api/0/config", bridge.base_url()))
+                    .json(&link_button))
+            })
+            .await?
+            .error_for_status()?;
+        }
         let gen_key_result = match self
-            .session
-            .post(format!("https://{}/api", &self.ip))
-            .json(&crate::resources::device::DeviceType {
-                app_name,
-                instance_name,
+            .send(|bridge| {
+                Ok(bridge
+                    .session
+                    .request(Method::POST, format!("{}/api", bridge.base_url()))
+                    .json(&device_type))
             })
-            .send()
             .await?
             .json::<Vec<GenKeyResult>>()
             .await?
@@ -171,13 +374,17 @@ impl Bridge {
             .remove(0)
         {
             GenKeyResult::Success(s) => s,
-            GenKeyResult::Error(e) => return Err(e.clone().into()),
+            GenKeyResult::Error(e) => {
+                tracing::warn!(bridge_id = %self.config.bridgeid, error = %e, "generating app key failed");
+                return Err(e.clone().into());
+            }
         };
+        tracing::info!(bridge_id = %self.config.bridgeid, app_name, instance_name, "generated app key");
 
         Ok((
             Self {
                 app_key: Some(gen_key_result.username.clone()),
-                client_key: Some(gen_key_result.clientkey),
+                client_key: gen_key_result.clientkey,
                 ..self
             },
             gen_key_result.username.clone(),
@@ -185,6 +392,309 @@ impl Bridge {
     }
 }
 
+impl Bridge {
+    /// Sends the request `build` creates, when the bridge can't be reached the watchdog
+    /// (if any) tries to find it at a new address and the request is built and sent again.
+    pub(crate) async fn send<F>(&self, build: F) -> Result<Response, Error>
+    where
+        F: Fn(&Self) -> Result<RequestBuilder, Error>,
+    {
+        let bridge_id = Some(self.config.bridgeid.as_str());
+        if let Some(remote) = &self.remote {
+            let token = remote.access_token().await?;
+            let req = build(self)?.bearer_auth(token);
+            return self.session.send_for(bridge_id, req).await;
+        }
+        let watchdog = match &self.watchdog {
+            Some(watchdog) => watchdog,
+            None => return self.session.send_for(bridge_id, build(self)?).await,
+        };
+        let ip = self.ip();
+        match self.session.send_for(bridge_id, build(self)?).await {
+            Err(Error::Reqwest(e)) if e.is_connect() => {
+                if !watchdog.relocate(self, ip).await {
+                    return Err(e.into());
+                }
+                self.session.send_for(bridge_id, build(self)?).await
+            }
+            res => res,
+        }
+    }
+}
+
+/// Serializable snapshot of a [`Bridge`], store it to skip discovery and authentication the
+/// next time your app starts.
+///
+/// ```no_run
+/// use lightrary::resources::{Bridge, BridgeDescriptor};
+///
+/// # fn run(bridge: Bridge) -> Result<(), Box<dyn std::error::Error>> {
+/// let saved = serde_json::to_string(&bridge.descriptor())?;
+/// // ...
+/// let bridge = serde_json::from_str::<BridgeDescriptor>(&saved)?.into_bridge()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The tokens of a bridge reached through the [remote api](crate::remote) aren't part of
+/// the descriptor, persist those separately and restore the bridge with
+/// [`into_remote_bridge`](BridgeDescriptor::into_remote_bridge).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BridgeDescriptor {
+    id: Option<String>,
+    ip: Ipv4Addr,
+    port: u16,
+    app_key: Option<String>,
+    client_key: Option<String>,
+    // descriptors saved before remote bridges were recorded are all local ones
+    #[serde(default)]
+    remote: bool,
+    config: BridgeConfig,
+}
+
+impl BridgeDescriptor {
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn ip(&self) -> Ipv4Addr {
+        self.ip
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn app_key(&self) -> Option<&str> {
+        self.app_key.as_deref()
+    }
+
+    pub fn client_key(&self) -> Option<&str> {
+        self.client_key.as_deref()
+    }
+
+    /// Whether the bridge was reached through the [remote api](crate::remote).
+    pub fn is_remote(&self) -> bool {
+        self.remote
+    }
+
+    /// The bridge configuration as it was when the descriptor was taken.
+    pub fn config(&self) -> &BridgeConfig {
+        &self.config
+    }
+
+    /// Recreates the bridge with a new session, without contacting it.
+    ///
+    /// Fails with [`Error::RemoteDescriptor`] for a bridge of the remote api.
+    pub fn into_bridge(self) -> Result<Bridge, Error> {
+        self.into_bridge_with(&Session::new()?)
+    }
+
+    /// Recreates the bridge using (a clone of) your own configured session.
+    ///
+    /// Fails with [`Error::RemoteDescriptor`] for a bridge of the remote api.
+    pub fn into_bridge_with(self, session: &Session) -> Result<Bridge, Error> {
+        if self.remote {
+            return Err(Error::RemoteDescriptor);
+        }
+        Ok(self.restore(session.clone(), None))
+    }
+
+    /// Recreates a bridge of the remote api, routing its requests through `remote` again.
+    pub fn into_remote_bridge(self, remote: &Remote) -> Bridge {
+        self.restore(remote.session().clone(), Some(remote.clone()))
+    }
+
+    fn restore(self, session: Session, remote: Option<Remote>) -> Bridge {
+        Bridge {
+            id: self.id,
+            ip: Arc::new(RwLock::new(self.ip)),
+            port: self.port,
+            app_key: self.app_key,
+            client_key: self.client_key,
+            session,
+            watchdog: None,
+            remote,
+            config: self.config,
+        }
+    }
+}
+
+impl From<&Bridge> for BridgeDescriptor {
+    fn from(bridge: &Bridge) -> Self {
+        bridge.descriptor()
+    }
+}
+
+// v1 api plumbing, every resource module goes through these so there's only a single
+// place where urls are built and bridge errors are unpacked
+impl Bridge {
+    fn v1_url(&self, path: &str) -> Result<String, Error> {
+        let app_key = self.app_key.as_deref().ok_or(Error::MissingAppKey)?;
+        Ok(format!("{}/api/{}{}", self.base_url(), app_key, path))
+    }
+
+    pub(crate) async fn v1_get<T>(&self, path: &str) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let value = self
+            .send(|bridge| Ok(bridge.session.get(bridge.v1_url(path)?)))
+            .await?
+            .json::<serde_json::Value>()
+            .await?;
+
+        // the v1 api responds with an array of errors instead of the requested object when
+        // something is wrong, for example when the app key is no longer whitelisted
+        if let Some(e) = first_error(&value) {
+            return Err(e.into());
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Fetches a v1 collection such as `/schedules`, which the bridge keys by id, sorted by id.
+    pub(crate) async fn v1_list<T>(&self, path: &str) -> Result<Vec<(String, T)>, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let mut list = self
+            .v1_get::<HashMap<String, T>>(path)
+            .await?
+            .into_iter()
+            .collect::<Vec<_>>();
+        // ids are numeric but sent as strings, sort them numerically so `10` comes after `9`
+        list.sort_by(|(a, _), (b, _)| (a.len(), a).cmp(&(b.len(), b)));
+        Ok(list)
+    }
+
+    /// Creates a v1 resource and returns the id the bridge assigned to it.
+    pub(crate) async fn v1_create<B>(&self, path: &str, body: &B) -> Result<String, Error>
+    where
+        B: Serialize + ?Sized,
+    {
+        #[derive(Deserialize)]
+        struct Created {
+            id: String,
+        }
+
+        let created = self
+            .v1_send(Method::POST, path, Some(body))
+            .await?
+            .into_iter()
+            .find_map(|value| Created::deserialize(value).ok());
+        created
+            .map(|created| created.id)
+            .ok_or_else(|| Error::UnexpectedResponse("missing id of created resource".into()))
+    }
+
+    /// Sends a write request and returns every `success` entry the bridge responded with.
+    pub(crate) async fn v1_send<B>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&B>,
+    ) -> Result<Vec<serde_json::Value>, Error>
+    where
+        B: Serialize + ?Sized,
+    {
+        let results = self
+            .send(|bridge| {
+                let req = bridge.session.request(method.clone(), bridge.v1_url(path)?);
+                Ok(match body {
+                    Some(body) => req.json(body),
+                    None => req,
+                })
+            })
+            .await?
+            .json::<Vec<ApiResult<serde_json::Value>>>()
+            .await?;
+
+        let mut success = Vec::with_capacity(results.len());
+        for result in results {
+            match result {
+                ApiResult::Success(value) => success.push(value),
+                ApiResult::Error(e) => return Err(e.into()),
+            }
+        }
+        Ok(success)
+    }
+}
+
+// CLIP v2 plumbing, authenticated through the `hue-application-key` header instead of the url
+impl Bridge {
+    pub(crate) fn v2_request(&self, method: Method, path: &str) -> Result<RequestBuilder, Error> {
+        self.require(Capability::ClipV2)?;
+        let app_key = self.app_key.as_deref().ok_or(Error::MissingAppKey)?;
+        Ok(self
+            .session
+            .request(method, format!("{}{}", self.base_url(), path))
+            .header("hue-application-key", app_key))
+    }
+
+    /// Fetches all resources of `rtype`, e.g. `device_software_update`.
+    pub(crate) async fn v2_get<T>(&self, rtype: &str) -> Result<Vec<T>, Error>
+    where
+        T: DeserializeOwned,
+    {
+        self.v2_get_path(&format!("/clip/v2/resource/{}", rtype))
+            .await
+    }
+
+    /// Fetches every resource the bridge has, regardless of their type.
+    pub(crate) async fn v2_get_all<T>(&self) -> Result<Vec<T>, Error>
+    where
+        T: DeserializeOwned,
+    {
+        self.v2_get_path("/clip/v2/resource").await
+    }
+
+    async fn v2_get_path<T>(&self, path: &str) -> Result<Vec<T>, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let res = self
+            .send(|bridge| bridge.v2_request(Method::GET, path))
+            .await?
+            .json::<ClipResponse<T>>()
+            .await?;
+
+        res.into_result()
+    }
+
+    /// Sends a write request and returns the identifiers of every affected resource.
+    pub(crate) async fn v2_send<B>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&B>,
+    ) -> Result<Vec<ResourceIdentifier>, Error>
+    where
+        B: Serialize + ?Sized,
+    {
+        let path = format!("/clip/v2/resource{}", path);
+        let res = self
+            .send(|bridge| {
+                let req = bridge.v2_request(method.clone(), &path)?;
+                Ok(match body {
+                    Some(body) => req.json(body),
+                    None => req,
+                })
+            })
+            .await?
+            .json::<ClipResponse<ResourceIdentifier>>()
+            .await?;
+
+        res.into_result()
+    }
+}
+
+fn first_error(value: &serde_json::Value) -> Option<ApiError> {
+    value
+        .as_array()?
+        .iter()
+        .find_map(|entry| ApiError::deserialize(entry.get("error")?).ok())
+}
+
 // // TODO: rate limiting on all the requests
 // impl Bridge {
 //     pub async fn lights<F>(&mut self, f: F) -> Result<(), Error>
@@ -221,3 +731,89 @@ impl Bridge {
 //         Ok(())
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::remote::{RemoteApp, Tokens};
+
+    const DESCRIPTOR: &str = r#"{
+        "id": "001788fffe6c2a3b",
+        "ip": "192.168.1.2",
+        "port": 443,
+        "app_key": "app-key",
+        "client_key": null,
+        "config": {
+            "name": "Test bridge",
+            "datastoreversion": "131",
+            "swversion": "1953188020",
+            "apiversion": "1.56.0",
+            "mac": "00:17:88:6c:2a:3b",
+            "bridgeid": "001788FFFE6C2A3B",
+            "factorynew": false,
+            "modelid": "BSB002",
+            "starterkitid": ""
+        }
+    }"#;
+
+    fn remote() -> Remote {
+        let tokens = serde_json::from_value::<Tokens>(serde_json::json!({
+            "access_token": "access",
+            "refresh_token": "refresh",
+            "expires_at": u64::MAX,
+        }))
+        .unwrap();
+        let session = Session::builder()
+            .accept_invalid_certs(false)
+            .build()
+            .unwrap();
+        Remote::from_tokens(RemoteApp::new("id", "secret"), &session, tokens).unwrap()
+    }
+
+    #[test]
+    fn local_descriptor() {
+        // saved before remote bridges were recorded
+        let descriptor = serde_json::from_str::<BridgeDescriptor>(DESCRIPTOR).unwrap();
+        assert!(!descriptor.is_remote());
+
+        let bridge = descriptor.into_bridge().unwrap();
+        assert!(!bridge.is_remote());
+        assert_eq!(bridge.ip(), Ipv4Addr::new(192, 168, 1, 2));
+        assert_eq!(bridge.base_url(), "https://192.168.1.2:443");
+        assert!(!bridge.descriptor().is_remote());
+    }
+
+    #[test]
+    fn remote_descriptor() {
+        let bridge = serde_json::from_str::<BridgeDescriptor>(DESCRIPTOR)
+            .unwrap()
+            .into_remote_bridge(&remote());
+        assert!(bridge.is_remote());
+        assert_eq!(bridge.base_url(), "https://api.meethue.com/route");
+        assert_eq!(bridge.app_key(), Some("app-key"));
+
+        // survives a round trip, but can't be restored without the remote
+        let saved = serde_json::to_string(&bridge.descriptor()).unwrap();
+        let descriptor = serde_json::from_str::<BridgeDescriptor>(&saved).unwrap();
+        assert!(descriptor.is_remote());
+        assert!(matches!(
+            descriptor.clone().into_bridge(),
+            Err(Error::RemoteDescriptor)
+        ));
+        assert!(descriptor.into_remote_bridge(&remote()).is_remote());
+    }
+
+    #[test]
+    fn clones_share_address() {
+        let bridge = serde_json::from_str::<BridgeDescriptor>(DESCRIPTOR)
+            .unwrap()
+            .into_bridge()
+            .unwrap();
+        let keyed = bridge.clone().with_key("other-key".into());
+
+        *bridge.ip.write().unwrap() = Ipv4Addr::new(192, 168, 1, 7);
+        assert_eq!(keyed.ip(), Ipv4Addr::new(192, 168, 1, 7));
+        assert_eq!(bridge.app_key(), Some("app-key"));
+        assert_eq!(keyed.app_key(), Some("other-key"));
+    }
+}
@@ -0,0 +1,85 @@
+use crate::error::Error;
+use crate::resources::{Bridge, BridgeConfig};
+use std::fmt;
+
+/// Feature of the bridge api which isn't available on every bridge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// The CLIP v2 api at `/clip/v2`.
+    ClipV2,
+    /// The CLIP v2 event stream, see [`Bridge::events`].
+    EventStream,
+}
+
+impl Capability {
+    /// First api version the capability shipped with.
+    pub(crate) fn since(self) -> (u32, u32, u32) {
+        match self {
+            Capability::ClipV2 | Capability::EventStream => (1, 48, 0),
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Capability::ClipV2 => "the CLIP v2 api",
+            Capability::EventStream => "the event stream",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What a bridge supports, derived from the api version and model of its [`BridgeConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    api_version: (u32, u32, u32),
+    // the first (round) bridge never got the CLIP v2 api, whatever its api version
+    v1_only: bool,
+}
+
+impl Capabilities {
+    pub fn from_config(config: &BridgeConfig) -> Self {
+        Self {
+            api_version: config.api_version_parts().unwrap_or_default(),
+            v1_only: config.model_id() == "BSB001",
+        }
+    }
+
+    /// Api version as `(major, minor, patch)`, `(0, 0, 0)` when the bridge reported one that
+    /// couldn't be parsed.
+    pub fn api_version(&self) -> (u32, u32, u32) {
+        self.api_version
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        !self.v1_only && self.api_version >= capability.since()
+    }
+
+    pub fn clip_v2(&self) -> bool {
+        self.supports(Capability::ClipV2)
+    }
+
+    pub fn event_stream(&self) -> bool {
+        self.supports(Capability::EventStream)
+    }
+
+}
+
+impl Bridge {
+    /// What this bridge supports, as of the configuration it was authenticated with.
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::from_config(&self.config)
+    }
+
+    /// Fails with [`Error::Unsupported`] when the bridge doesn't support `capability`.
+    pub(crate) fn require(&self, capability: Capability) -> Result<(), Error> {
+        match self.capabilities().supports(capability) {
+            true => Ok(()),
+            false => Err(Error::Unsupported {
+                capability,
+                api_version: self.config.api_version().to_owned(),
+            }),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use crate::error::{ClipError, Error};
+use serde::{Deserialize, Serialize};
+
+/// Reference to a CLIP v2 resource, e.g. the device that owns a light.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ResourceIdentifier {
+    pub rid: String,
+    pub rtype: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ClipResponse<T> {
+    #[serde(default)]
+    errors: Vec<ClipErrorEntry>,
+    #[serde(default = "Vec::new")]
+    data: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClipErrorEntry {
+    description: String,
+}
+
+impl<T> ClipResponse<T> {
+    // the bridge can partially apply a request, though if anything went wrong we
+    // treat the whole request as failed
+    pub(crate) fn into_result(self) -> Result<Vec<T>, Error> {
+        if self.errors.is_empty() {
+            Ok(self.data)
+        } else {
+            Err(ClipError {
+                descriptions: self.errors.into_iter().map(|e| e.description).collect(),
+            }
+            .into())
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use crate::error::Error;
+use crate::resources::{Bridge, ResourceIdentifier};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectivityStatus {
+    Connected,
+    Disconnected,
+    ConnectivityIssue,
+    UnidirectionalIncoming,
+    #[serde(other)]
+    Unknown,
+}
+
+impl ConnectivityStatus {
+    /// Whether the bridge can (reliably) send commands to the device.
+    pub fn is_reachable(&self) -> bool {
+        *self == ConnectivityStatus::Connected
+    }
+}
+
+/// Zigbee connectivity of a device (CLIP v2 `zigbee_connectivity`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct ZigbeeConnectivity {
+    id: String,
+    owner: ResourceIdentifier,
+    status: ConnectivityStatus,
+    mac_address: String,
+}
+
+impl ZigbeeConnectivity {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn owner(&self) -> &ResourceIdentifier {
+        &self.owner
+    }
+
+    pub fn status(&self) -> ConnectivityStatus {
+        self.status
+    }
+
+    pub fn mac_address(&self) -> &str {
+        &self.mac_address
+    }
+}
+
+/// Zigbee Green Power connectivity, used by battery-less devices such as the Hue Tap
+/// (CLIP v2 `zgp_connectivity`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct ZgpConnectivity {
+    id: String,
+    owner: ResourceIdentifier,
+    status: ConnectivityStatus,
+    source_id: String,
+}
+
+impl ZgpConnectivity {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn owner(&self) -> &ResourceIdentifier {
+        &self.owner
+    }
+
+    pub fn status(&self) -> ConnectivityStatus {
+        self.status
+    }
+
+    pub fn source_id(&self) -> &str {
+        &self.source_id
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatteryState {
+    Normal,
+    Low,
+    Critical,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct PowerState {
+    pub battery_state: Option<BatteryState>,
+    /// Percentage between 0 and 100.
+    pub battery_level: Option<u8>,
+}
+
+impl PowerState {
+    pub fn is_low(&self) -> bool {
+        matches!(
+            self.battery_state,
+            Some(BatteryState::Low | BatteryState::Critical)
+        )
+    }
+}
+
+/// Power state of a battery powered device (CLIP v2 `device_power`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct DevicePower {
+    id: String,
+    owner: ResourceIdentifier,
+    power_state: PowerState,
+}
+
+impl DevicePower {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn owner(&self) -> &ResourceIdentifier {
+        &self.owner
+    }
+
+    pub fn power_state(&self) -> PowerState {
+        self.power_state
+    }
+}
+
+/// Connectivity and battery state of a single device.
+#[derive(Debug, Clone)]
+pub struct DeviceHealth {
+    device: ResourceIdentifier,
+    name: Option<String>,
+    mac_address: Option<String>,
+    status: Option<ConnectivityStatus>,
+    power: Option<PowerState>,
+}
+
+impl DeviceHealth {
+    pub fn device(&self) -> &ResourceIdentifier {
+        &self.device
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn mac_address(&self) -> Option<&str> {
+        self.mac_address.as_deref()
+    }
+
+    /// `None` for devices that don't communicate through zigbee, like the bridge itself.
+    pub fn status(&self) -> Option<ConnectivityStatus> {
+        self.status
+    }
+
+    /// `None` for devices that aren't battery powered.
+    pub fn power(&self) -> Option<PowerState> {
+        self.power
+    }
+
+    pub fn is_unreachable(&self) -> bool {
+        self.status.is_some_and(|status| !status.is_reachable())
+    }
+
+    pub fn is_low_battery(&self) -> bool {
+        self.power.is_some_and(|power| power.is_low())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HealthReport(pub(crate) Vec<DeviceHealth>);
+
+impl HealthReport {
+    pub fn iter(&self) -> std::slice::Iter<'_, DeviceHealth> {
+        self.0.iter()
+    }
+
+    pub fn unreachable(&self) -> impl Iterator<Item = &DeviceHealth> {
+        self.0.iter().filter(|device| device.is_unreachable())
+    }
+
+    pub fn low_battery(&self) -> impl Iterator<Item = &DeviceHealth> {
+        self.0.iter().filter(|device| device.is_low_battery())
+    }
+
+    /// Whether every device is reachable and none of them are low on battery.
+    pub fn is_healthy(&self) -> bool {
+        self.unreachable().next().is_none() && self.low_battery().next().is_none()
+    }
+}
+
+impl IntoIterator for HealthReport {
+    type Item = DeviceHealth;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+// only used to put a name to the devices in the health report
+#[derive(Deserialize)]
+struct DeviceName {
+    id: String,
+    metadata: DeviceMetadata,
+}
+
+#[derive(Deserialize)]
+struct DeviceMetadata {
+    name: String,
+}
+
+impl Bridge {
+    pub async fn zigbee_connectivity(&self) -> Result<Vec<ZigbeeConnectivity>, Error> {
+        self.v2_get("zigbee_connectivity").await
+    }
+
+    pub async fn zgp_connectivity(&self) -> Result<Vec<ZgpConnectivity>, Error> {
+        self.v2_get("zgp_connectivity").await
+    }
+
+    pub async fn device_power(&self) -> Result<Vec<DevicePower>, Error> {
+        self.v2_get("device_power").await
+    }
+
+    /// Collects the connectivity and battery state of every device on this bridge.
+    pub async fn health_report(&self) -> Result<HealthReport, Error> {
+        let (names, zigbee, zgp, power) = futures::try_join!(
+            self.v2_get::<DeviceName>("device"),
+            self.zigbee_connectivity(),
+            self.zgp_connectivity(),
+            self.device_power(),
+        )?;
+
+        // keyed by device id, sorted so the report is stable between runs
+        let mut devices = BTreeMap::new();
+        for conn in zigbee {
+            let device = health_entry(&mut devices, conn.owner);
+            device.status = Some(conn.status);
+            device.mac_address = Some(conn.mac_address);
+        }
+        for conn in zgp {
+            health_entry(&mut devices, conn.owner).status = Some(conn.status);
+        }
+        for power in power {
+            health_entry(&mut devices, power.owner).power = Some(power.power_state);
+        }
+        for device in names {
+            if let Some(health) = devices.get_mut(&device.id) {
+                health.name = Some(device.metadata.name);
+            }
+        }
+
+        Ok(HealthReport(devices.into_values().collect()))
+    }
+}
+
+fn health_entry(
+    devices: &mut BTreeMap<String, DeviceHealth>,
+    owner: ResourceIdentifier,
+) -> &mut DeviceHealth {
+    devices
+        .entry(owner.rid.clone())
+        .or_insert_with(|| DeviceHealth {
+            device: owner,
+            name: None,
+            mac_address: None,
+            status: None,
+            power: None,
+        })
+}
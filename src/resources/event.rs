@@ -0,0 +1,269 @@
+use crate::error::{Error, Redacted};
+use crate::resources::{Bridge, Capability};
+use crate::transport::StatusCode;
+use futures::future;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+// how long to wait before reconnecting after the bridge closed the event stream or
+// the connection failed, the bridge doesn't like being hammered with reconnects
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Single event pushed by the bridge over the CLIP v2 event stream.
+///
+/// `data` contains (partial) resources which changed, only the fields that changed
+/// are present apart from `id` and `type`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Event {
+    pub id: String,
+    #[serde(rename = "creationtime")]
+    pub creation_time: String,
+    #[serde(rename = "type")]
+    pub kind: EventKind,
+    pub data: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Add,
+    Update,
+    Delete,
+    Error,
+}
+
+impl Event {
+    /// Deserializes every resource in this event that is of type `rtype`.
+    pub fn resources<'a, T>(&'a self, rtype: &'a str) -> impl Iterator<Item = Result<T, Error>> + 'a
+    where
+        T: DeserializeOwned,
+    {
+        self.data
+            .iter()
+            .filter(move |data| data.get("type").and_then(|t| t.as_str()) == Some(rtype))
+            .map(|data| T::deserialize(data).map_err(Error::from))
+    }
+}
+
+struct EventStream<'a> {
+    bridge: &'a Bridge,
+    body: Option<BoxStream<'static, Result<Vec<u8>, Error>>>,
+    buf: Vec<u8>,
+    pending: VecDeque<Event>,
+    reconnecting: bool,
+    done: bool,
+}
+
+impl Bridge {
+    /// Listens to the CLIP v2 event stream of this bridge.
+    ///
+    /// Whenever the connection drops or can't be made because of a transient problem (the
+    /// bridge being unreachable, timing out or answering with a `5xx`) the stream yields the
+    /// error (if any) and reconnects, it only ends after yielding an error that won't go away
+    /// by retrying, e.g. [`Error::MissingAppKey`] or a rejected app key. Drop the stream to stop
+    /// listening. Bridges without an event stream only yield [`Error::Unsupported`].
+    pub fn events(&self) -> impl Stream<Item = Result<Event, Error>> + Send + '_ {
+        if let Err(e) = self.require(Capability::EventStream) {
+            return stream::once(future::ready(Err(e))).left_stream();
+        }
+        let state = EventStream {
+            bridge: self,
+            body: None,
+            buf: Vec::new(),
+            pending: VecDeque::new(),
+            reconnecting: false,
+            done: false,
+        };
+
+        stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(event) = state.pending.pop_front() {
+                    return Some((Ok(event), state));
+                }
+                if state.done {
+                    return None;
+                }
+
+                let body = match &mut state.body {
+                    Some(body) => body,
+                    None => {
+                        if state.reconnecting {
+                            #[cfg(feature = "metrics")]
+                            crate::telemetry::event_stream_reconnect(&state.bridge.config.bridgeid);
+                            tracing::debug!(bridge_id = %state.bridge.config.bridgeid, delay = ?RECONNECT_DELAY, "reconnecting to the event stream");
+                            tokio::time::sleep(RECONNECT_DELAY).await;
+                        }
+                        state.reconnecting = true;
+                        state.buf.clear();
+                        match state.bridge.connect_events().await {
+                            Ok(body) => {
+                                tracing::debug!(bridge_id = %state.bridge.config.bridgeid, "connected to the event stream");
+                                state.body.insert(body)
+                            }
+                            Err(e) => {
+                                tracing::warn!(bridge_id = %state.bridge.config.bridgeid, error = %Redacted(&e), "connecting to the event stream failed");
+                                state.done = !transient(&e);
+                                return Some((Err(e), state));
+                            }
+                        }
+                    }
+                };
+
+                match body.next().await {
+                    Some(Ok(chunk)) => {
+                        if let Err(e) = drain_messages(&mut state.buf, &chunk, &mut state.pending) {
+                            return Some((Err(e), state));
+                        }
+                    }
+                    Some(Err(e)) => {
+                        tracing::warn!(bridge_id = %state.bridge.config.bridgeid, error = %e, "event stream dropped");
+                        state.body = None;
+                        return Some((Err(e), state));
+                    }
+                    None => {
+                        tracing::debug!(bridge_id = %state.bridge.config.bridgeid, "bridge closed the event stream");
+                        state.body = None;
+                    }
+                }
+            }
+        })
+        .right_stream()
+    }
+
+    async fn connect_events(&self) -> Result<BoxStream<'static, Result<Vec<u8>, Error>>, Error> {
+        let res = self
+            .send(|bridge| {
+                Ok(bridge
+                    .v2_request(Method::GET, "/eventstream/clip/v2")?
+                    .header("Accept", "text/event-stream"))
+            })
+            .await?
+            .error_for_status()?;
+
+        Ok(res.bytes_stream())
+    }
+}
+
+// whether reconnecting might get rid of the error, the bridge being busy or unreachable
+// will pass but a rejected app key or a missing capability won't
+fn transient(e: &Error) -> bool {
+    match e {
+        Error::Status(status) => {
+            status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+        }
+        Error::Reqwest(e) => e.is_connect() || e.is_timeout() || e.is_body() || e.is_request(),
+        Error::Io(_) | Error::Transport(_) => true,
+        _ => false,
+    }
+}
+
+// appends a chunk of the body to the buffer and parses every complete server-sent event
+// message in it, the bridge also sends `: hi` comments which are ignored
+fn drain_messages(
+    buf: &mut Vec<u8>,
+    chunk: &[u8],
+    pending: &mut VecDeque<Event>,
+) -> Result<(), Error> {
+    buf.extend(chunk.iter().filter(|b| **b != b'\r'));
+    while let Some(end) = buf.windows(2).position(|w| w == b"\n\n") {
+        let message = buf.drain(..end + 2).collect::<Vec<_>>();
+        let message = String::from_utf8_lossy(&message);
+        let data = message
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(|data| data.strip_prefix(' ').unwrap_or(data))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if !data.trim().is_empty() {
+            pending.extend(serde_json::from_str::<Vec<Event>>(&data)?);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EVENT: &str = r#"[{"creationtime":"2022-05-10T10:00:00Z","data":[{"id":"abc","type":"light","on":{"on":true}}],"id":"e1","type":"update"}]"#;
+
+    fn drain(chunks: &[&str]) -> Vec<Event> {
+        let mut buf = Vec::new();
+        let mut pending = VecDeque::new();
+        for chunk in chunks {
+            drain_messages(&mut buf, chunk.as_bytes(), &mut pending).unwrap();
+        }
+        pending.into()
+    }
+
+    #[test]
+    fn single_message() {
+        let events = drain(&[&format!("id: 1:0\ndata: {EVENT}\n\n")]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, "e1");
+        assert_eq!(events[0].kind, EventKind::Update);
+        assert_eq!(events[0].data[0]["on"]["on"], true);
+    }
+
+    #[test]
+    fn message_split_over_chunks() {
+        let message = format!("id: 1:0\ndata: {EVENT}\n\n");
+        let (first, rest) = message.split_at(20);
+        let (second, third) = rest.split_at(rest.len() - 1);
+
+        let mut buf = Vec::new();
+        let mut pending = VecDeque::new();
+        drain_messages(&mut buf, first.as_bytes(), &mut pending).unwrap();
+        drain_messages(&mut buf, second.as_bytes(), &mut pending).unwrap();
+        assert!(pending.is_empty());
+        drain_messages(&mut buf, third.as_bytes(), &mut pending).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn multiple_messages_in_one_chunk() {
+        let events = drain(&[&format!("data: {EVENT}\n\ndata: {EVENT}\n\n")]);
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn multi_line_data() {
+        let (start, end) = EVENT.split_at(EVENT.find("\"data\"").unwrap());
+        let events = drain(&[&format!("id: 1:0\ndata: {start}\ndata: {end}\n\n")]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data.len(), 1);
+    }
+
+    #[test]
+    fn comments_are_ignored() {
+        let events = drain(&[": hi\n\n", &format!(": hi\ndata: {EVENT}\n\n")]);
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        let events = drain(&[&format!("id: 1:0\r\ndata: {EVENT}\r"), "\n\r\n"]);
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn invalid_json() {
+        let mut buf = Vec::new();
+        let mut pending = VecDeque::new();
+        let res = drain_messages(&mut buf, b"data: [{\n\n", &mut pending);
+        assert!(matches!(res, Err(Error::Json(_))));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn permanent_errors_are_not_transient() {
+        assert!(!transient(&Error::MissingAppKey));
+        assert!(transient(&Error::Transport("connection reset".into())));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::error::Error;
+use crate::resources::{Bridge, Bridges};
+use futures::future;
+use std::future::Future;
+
+/// Outcome of an operation on a single bridge of a [`Bridges`] fleet.
+#[derive(Debug)]
+pub struct BridgeResult<'a, T> {
+    pub bridge: &'a Bridge,
+    pub result: Result<T, Error>,
+}
+
+/// Resources gathered from every bridge in a fleet, see [`Bridges::merge`].
+#[derive(Debug)]
+pub struct Merged<'a, T> {
+    /// Every resource along with the bridge it lives on.
+    pub resources: Vec<(&'a Bridge, T)>,
+    /// Bridges the resources couldn't be gathered from.
+    pub failed: Vec<BridgeResult<'a, ()>>,
+}
+
+impl Bridges {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn push(&mut self, bridge: Bridge) {
+        self.0.push(bridge);
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Bridge> {
+        self.0.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Bridge> {
+        self.0.iter_mut()
+    }
+
+    /// Looks up a bridge by its bridge id, e.g. `001788fffe6c2a3b`.
+    pub fn get(&self, bridge_id: &str) -> Option<&Bridge> {
+        self.0
+            .iter()
+            .find(|bridge| bridge.config.bridgeid.eq_ignore_ascii_case(bridge_id))
+    }
+
+    pub fn get_mut(&mut self, bridge_id: &str) -> Option<&mut Bridge> {
+        self.0
+            .iter_mut()
+            .find(|bridge| bridge.config.bridgeid.eq_ignore_ascii_case(bridge_id))
+    }
+
+    /// Looks up a bridge by the name it was given in the Hue app.
+    pub fn name(&self, name: &str) -> Option<&Bridge> {
+        self.0.iter().find(|bridge| bridge.config.name() == name)
+    }
+
+    /// Removes a bridge from the fleet by its bridge id.
+    pub fn remove(&mut self, bridge_id: &str) -> Option<Bridge> {
+        let index = self
+            .0
+            .iter()
+            .position(|bridge| bridge.config.bridgeid.eq_ignore_ascii_case(bridge_id))?;
+        Some(self.0.remove(index))
+    }
+
+    /// Runs `f` on every bridge concurrently, one failing bridge doesn't affect the others.
+    ///
+    /// ```no_run
+    /// use lightrary::resources::Bridges;
+    ///
+    /// # async fn run(bridges: Bridges) {
+    /// for res in bridges.run(|bridge| bridge.health_report()).await {
+    ///     match res.result {
+    ///         Ok(report) => println!("{}: healthy: {}", res.bridge.config().name(), report.is_healthy()),
+    ///         Err(e) => eprintln!("{}: {}", res.bridge.config().name(), e),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub async fn run<'a, F, Fut, T>(&'a self, f: F) -> Vec<BridgeResult<'a, T>>
+    where
+        F: Fn(&'a Bridge) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let results = future::join_all(self.0.iter().map(&f)).await;
+        self.0
+            .iter()
+            .zip(results)
+            .map(|(bridge, result)| BridgeResult { bridge, result })
+            .collect()
+    }
+
+    /// Gathers resources from every bridge concurrently and treats them as one list.
+    ///
+    /// ```no_run
+    /// use lightrary::resources::Bridges;
+    ///
+    /// # async fn run(bridges: Bridges) {
+    /// let sensors = bridges.merge(|bridge| bridge.sensors()).await;
+    /// let kitchen = sensors
+    ///     .resources
+    ///     .iter()
+    ///     .filter(|(_, sensor)| sensor.name() == "Kitchen");
+    /// # }
+    /// ```
+    pub async fn merge<'a, F, Fut, I>(&'a self, f: F) -> Merged<'a, I::Item>
+    where
+        F: Fn(&'a Bridge) -> Fut,
+        Fut: Future<Output = Result<I, Error>>,
+        I: IntoIterator,
+    {
+        let mut merged = Merged {
+            resources: Vec::new(),
+            failed: Vec::new(),
+        };
+        for res in self.run(f).await {
+            match res.result {
+                Ok(resources) => merged
+                    .resources
+                    .extend(resources.into_iter().map(|r| (res.bridge, r))),
+                Err(e) => merged.failed.push(BridgeResult {
+                    bridge: res.bridge,
+                    result: Err(e),
+                }),
+            }
+        }
+        merged
+    }
+}
+
+impl From<Vec<Bridge>> for Bridges {
+    fn from(bridges: Vec<Bridge>) -> Self {
+        Self(bridges)
+    }
+}
+
+impl FromIterator<Bridge> for Bridges {
+    fn from_iter<I: IntoIterator<Item = Bridge>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl Extend<Bridge> for Bridges {
+    fn extend<I: IntoIterator<Item = Bridge>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl IntoIterator for Bridges {
+    type Item = Bridge;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Bridges {
+    type Item = &'a Bridge;
+    type IntoIter = std::slice::Iter<'a, Bridge>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
@@ -1,3 +1,6 @@
+use crate::error::Error;
+use crate::resources::{Bridge, ResourceIdentifier};
+use reqwest::Method;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -39,3 +42,193 @@ impl Light {
 struct State {
     on: bool,
 }
+
+/// Light as exposed by the CLIP v2 api, the v1 one is [`Light`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClipLight {
+    id: String,
+    owner: ResourceIdentifier,
+    metadata: LightMetadata,
+    on: On,
+    #[serde(default)]
+    dimming: Option<Dimming>,
+    #[serde(default)]
+    color_temperature: Option<ColorTemperature>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct LightMetadata {
+    name: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct On {
+    pub(crate) on: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct Dimming {
+    pub(crate) brightness: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct ColorTemperature {
+    // not set while the light shows a color outside of the color temperature range
+    pub(crate) mirek: Option<u16>,
+}
+
+impl ClipLight {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The device this light is part of.
+    pub fn owner(&self) -> &ResourceIdentifier {
+        &self.owner
+    }
+
+    pub fn name(&self) -> &str {
+        &self.metadata.name
+    }
+
+    pub fn on(&self) -> bool {
+        self.on.on
+    }
+
+    /// In percent, `None` for lights that can't be dimmed.
+    pub fn brightness(&self) -> Option<f64> {
+        self.dimming.map(|dimming| dimming.brightness)
+    }
+
+    /// Color temperature in mirek, `None` for lights without one or that show a color.
+    pub fn mirek(&self) -> Option<u16> {
+        self.color_temperature.and_then(|ct| ct.mirek)
+    }
+}
+
+/// Change to the state of a light, or of every light in a room through its grouped light.
+///
+/// ```no_run
+/// use lightrary::resources::{Bridge, LightUpdate};
+///
+/// # async fn run(bridge: Bridge) -> Result<(), lightrary::error::Error> {
+/// let update = LightUpdate::new().with_on(true).with_brightness(40.0).with_mirek(366);
+/// for light in bridge.lights().await? {
+///     bridge.update_light(light.id(), &update).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LightUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    on: Option<On>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimming: Option<Dimming>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color_temperature: Option<ColorTemperature>,
+}
+
+impl LightUpdate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_on(mut self, on: bool) -> Self {
+        self.on = Some(On { on });
+        self
+    }
+
+    /// In percent, `0.0` is the lowest brightness the light can do while staying on.
+    pub fn with_brightness(mut self, brightness: f64) -> Self {
+        self.dimming = Some(Dimming { brightness });
+        self
+    }
+
+    /// Usually between `153` (cold) and `500` (warm).
+    pub fn with_mirek(mut self, mirek: u16) -> Self {
+        self.color_temperature = Some(ColorTemperature { mirek: Some(mirek) });
+        self
+    }
+}
+
+impl Bridge {
+    pub async fn lights(&self) -> Result<Vec<ClipLight>, Error> {
+        self.v2_get("light").await
+    }
+
+    pub async fn update_light(&self, id: &str, update: &LightUpdate) -> Result<(), Error> {
+        let path = format!("/light/{}", id);
+        self.v2_send(Method::PUT, &path, Some(update)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_lights() {
+        let light = serde_json::from_value::<ClipLight>(json!({
+            "id": "3f2a9c1e",
+            "type": "light",
+            "owner": { "rid": "8d1b7e20", "rtype": "device" },
+            "metadata": { "name": "Desk", "archetype": "desk_lamp" },
+            "on": { "on": true },
+            "dimming": { "brightness": 80.0, "min_dim_level": 0.2 },
+            "color_temperature": { "mirek": 366, "mirek_valid": true },
+        }))
+        .unwrap();
+        assert_eq!(light.id(), "3f2a9c1e");
+        assert_eq!(light.owner().rtype, "device");
+        assert_eq!(light.name(), "Desk");
+        assert!(light.on());
+        assert_eq!(light.brightness(), Some(80.0));
+        assert_eq!(light.mirek(), Some(366));
+
+        // a plug, and a color light showing a color
+        let plug = serde_json::from_value::<ClipLight>(json!({
+            "id": "a",
+            "owner": { "rid": "b", "rtype": "device" },
+            "metadata": { "name": "Plug" },
+            "on": { "on": false },
+        }))
+        .unwrap();
+        assert_eq!(plug.brightness(), None);
+        assert_eq!(plug.mirek(), None);
+        let colored = serde_json::from_value::<ClipLight>(json!({
+            "id": "c",
+            "owner": { "rid": "d", "rtype": "device" },
+            "metadata": { "name": "Strip" },
+            "on": { "on": true },
+            "color_temperature": { "mirek": null, "mirek_valid": false },
+        }))
+        .unwrap();
+        assert_eq!(colored.mirek(), None);
+    }
+
+    #[test]
+    fn only_send_changes() {
+        assert_eq!(serde_json::to_value(LightUpdate::new()).unwrap(), json!({}));
+        assert_eq!(
+            serde_json::to_value(LightUpdate::new().with_on(false)).unwrap(),
+            json!({ "on": { "on": false } })
+        );
+        assert_eq!(
+            serde_json::to_value(
+                LightUpdate::new()
+                    .with_on(true)
+                    .with_brightness(40.0)
+                    .with_mirek(366)
+            )
+            .unwrap(),
+            json!({
+                "on": { "on": true },
+                "dimming": { "brightness": 40.0 },
+                "color_temperature": { "mirek": 366 },
+            })
+        );
+    }
+}
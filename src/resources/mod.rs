@@ -1,6 +1,50 @@
+mod behavior;
 mod bridge;
+mod capabilities;
+mod clip;
+mod connectivity;
 pub(crate) mod device;
+#[cfg(feature = "eventstream")]
+mod event;
+mod fleet;
 mod light;
+mod resourcelink;
+mod room;
+mod rule;
+mod scene;
+mod schedule;
+mod search;
+mod sensor;
+mod software_update;
+mod watchdog;
+mod whitelist;
 
-pub use bridge::{Bridge, BridgeConfig, Bridges, UnauthBridge, UnauthBridges};
-pub use light::{Light, Lights};
+pub use behavior::{
+    BehaviorCategory, BehaviorInstance, BehaviorScript, BehaviorStatus, NewBehaviorInstance,
+};
+pub(crate) use bridge::fetch_config;
+pub use bridge::{Bridge, BridgeConfig, BridgeDescriptor, Bridges, UnauthBridge, UnauthBridges};
+pub use capabilities::{Capabilities, Capability};
+pub use clip::ResourceIdentifier;
+pub use connectivity::{
+    BatteryState, ConnectivityStatus, DeviceHealth, DevicePower, HealthReport, PowerState,
+    ZgpConnectivity, ZigbeeConnectivity,
+};
+#[cfg(feature = "eventstream")]
+pub use event::{Event, EventKind};
+pub use fleet::{BridgeResult, Merged};
+pub use light::{ClipLight, Light, LightUpdate, Lights};
+pub use resourcelink::{NewResourceLink, ResourceLink};
+pub use room::Room;
+pub use rule::{Action, ActionMethod, Condition, ConditionOperator, NewRule, Rule, RuleStatus};
+pub use scene::Scene;
+pub(crate) use schedule::relative_address;
+pub use schedule::{NewSchedule, Schedule, ScheduleStatus};
+pub use search::{LastScan, NewDevice, NewDevices, SearchKind};
+pub use sensor::{NewSensor, Sensor};
+pub use software_update::{
+    AutoInstall, DeviceSoftwareUpdate, DeviceSoftwareUpdateEvent, DeviceUpdateState,
+    SoftwareUpdate, UpdateState,
+};
+pub use watchdog::{Watchdog, WatchdogEvent};
+pub use whitelist::{Application, Applications};
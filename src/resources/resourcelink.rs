@@ -0,0 +1,134 @@
+use crate::error::Error;
+use crate::resources::Bridge;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// Groups resources (rules, schedules, sensors, scenes, ...) that belong together so apps
+/// can recognize and clean up their own configuration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResourceLink {
+    #[serde(skip)]
+    id: String,
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(rename = "classid")]
+    class_id: u16,
+    owner: Option<String>,
+    #[serde(default)]
+    recycle: bool,
+    links: Vec<String>,
+}
+
+impl ResourceLink {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Application defined id to recognize its own links.
+    pub fn class_id(&self) -> u16 {
+        self.class_id
+    }
+
+    /// App key of the application which created this link.
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    pub fn recycle(&self) -> bool {
+        self.recycle
+    }
+
+    /// Addresses of the linked resources, e.g. `/rules/3` or `/sensors/12`.
+    pub fn links(&self) -> &[String] {
+        &self.links
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NewResourceLink {
+    name: String,
+    description: String,
+    #[serde(rename = "type")]
+    t: &'static str,
+    #[serde(rename = "classid")]
+    class_id: u16,
+    recycle: bool,
+    links: Vec<String>,
+}
+
+impl NewResourceLink {
+    pub fn new(name: &str, class_id: u16, links: Vec<String>) -> Self {
+        Self {
+            name: name.to_owned(),
+            description: String::new(),
+            t: "Link",
+            class_id,
+            recycle: false,
+            links,
+        }
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_owned();
+        self
+    }
+
+    /// Whether the bridge deletes this link, and every linked resource with recycle set,
+    /// once any of the links are gone.
+    pub fn with_recycle(mut self, recycle: bool) -> Self {
+        self.recycle = recycle;
+        self
+    }
+}
+
+impl From<&ResourceLink> for NewResourceLink {
+    fn from(link: &ResourceLink) -> Self {
+        Self::new(&link.name, link.class_id, link.links.clone())
+            .with_description(&link.description)
+            .with_recycle(link.recycle)
+    }
+}
+
+#[derive(Serialize)]
+struct LinksChange<'a> {
+    links: &'a [String],
+}
+
+impl Bridge {
+    pub async fn resource_links(&self) -> Result<Vec<ResourceLink>, Error> {
+        Ok(self
+            .v1_list::<ResourceLink>("/resourcelinks")
+            .await?
+            .into_iter()
+            .map(|(id, link)| ResourceLink { id, ..link })
+            .collect())
+    }
+
+    /// Creates a new resource link and returns its id.
+    pub async fn create_resource_link(&self, link: &NewResourceLink) -> Result<String, Error> {
+        self.v1_create("/resourcelinks", link).await
+    }
+
+    /// Replaces all links of the resource link with `id`.
+    pub async fn set_resource_link_links(&self, id: &str, links: &[String]) -> Result<(), Error> {
+        let path = format!("/resourcelinks/{}", id);
+        self.v1_send(Method::PUT, &path, Some(&LinksChange { links }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_resource_link(&self, id: &str) -> Result<(), Error> {
+        let path = format!("/resourcelinks/{}", id);
+        self.v1_send::<()>(Method::DELETE, &path, None).await?;
+        Ok(())
+    }
+}
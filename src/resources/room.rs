@@ -0,0 +1,94 @@
+use crate::error::Error;
+use crate::resources::{Bridge, LightUpdate, ResourceIdentifier};
+use reqwest::Method;
+use serde::Deserialize;
+
+/// Room as set up in the Hue app (CLIP v2 `room`), its lights are controlled together through
+/// its [grouped light](Room::grouped_light).
+#[derive(Debug, Clone, Deserialize)]
+pub struct Room {
+    id: String,
+    metadata: RoomMetadata,
+    // the devices in the room
+    children: Vec<ResourceIdentifier>,
+    services: Vec<ResourceIdentifier>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RoomMetadata {
+    name: String,
+}
+
+impl Room {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.metadata.name
+    }
+
+    /// The devices in this room.
+    pub fn devices(&self) -> impl Iterator<Item = &ResourceIdentifier> {
+        self.children.iter()
+    }
+
+    /// Id of the grouped light to pass to [`Bridge::update_grouped_light`], `None` for a room
+    /// without lights.
+    pub fn grouped_light(&self) -> Option<&str> {
+        self.services
+            .iter()
+            .find(|service| service.rtype == "grouped_light")
+            .map(|service| service.rid.as_str())
+    }
+}
+
+impl Bridge {
+    pub async fn rooms(&self) -> Result<Vec<Room>, Error> {
+        self.v2_get("room").await
+    }
+
+    /// Changes every light of a room (or zone) at once.
+    pub async fn update_grouped_light(&self, id: &str, update: &LightUpdate) -> Result<(), Error> {
+        let path = format!("/grouped_light/{}", id);
+        self.v2_send(Method::PUT, &path, Some(update)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_rooms() {
+        let room = serde_json::from_value::<Room>(json!({
+            "id": "5c2d3a10",
+            "type": "room",
+            "metadata": { "name": "Office", "archetype": "office" },
+            "children": [
+                { "rid": "8d1b7e20", "rtype": "device" },
+                { "rid": "9e2c8f31", "rtype": "device" },
+            ],
+            "services": [{ "rid": "0a4b6c82", "rtype": "grouped_light" }],
+        }))
+        .unwrap();
+        assert_eq!(room.id(), "5c2d3a10");
+        assert_eq!(room.name(), "Office");
+        assert_eq!(room.devices().count(), 2);
+        assert_eq!(room.grouped_light(), Some("0a4b6c82"));
+    }
+
+    #[test]
+    fn room_without_lights() {
+        let room = serde_json::from_value::<Room>(json!({
+            "id": "5c2d3a10",
+            "metadata": { "name": "Hallway" },
+            "children": [],
+            "services": [],
+        }))
+        .unwrap();
+        assert_eq!(room.grouped_light(), None);
+    }
+}
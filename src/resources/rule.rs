@@ -0,0 +1,363 @@
+use crate::error::Error;
+use crate::resources::Bridge;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// Request the bridge sends to itself when a rule or schedule triggers.
+///
+/// Addresses are relative to the app key, e.g. `/groups/0/action` or `/sensors/5/state`.
+/// Schedules read from the bridge are the exception, see
+/// [`Schedule::command`](crate::resources::Schedule::command).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Action {
+    pub address: String,
+    pub method: ActionMethod,
+    pub body: serde_json::Value,
+}
+
+impl Action {
+    pub fn put(address: &str, body: serde_json::Value) -> Self {
+        Self {
+            address: address.to_owned(),
+            method: ActionMethod::Put,
+            body,
+        }
+    }
+
+    pub fn post(address: &str, body: serde_json::Value) -> Self {
+        Self {
+            address: address.to_owned(),
+            method: ActionMethod::Post,
+            body,
+        }
+    }
+
+    pub fn delete(address: &str) -> Self {
+        Self {
+            address: address.to_owned(),
+            method: ActionMethod::Delete,
+            body: serde_json::Value::Object(Default::default()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ActionMethod {
+    Put,
+    Post,
+    Delete,
+}
+
+/// Condition which has to be met for a rule to trigger, all conditions of a rule are combined
+/// with a logical and.
+///
+/// ```
+/// use lightrary::resources::Condition;
+///
+/// // dimmer switch "on" button pressed, but only in the evening
+/// let conditions = vec![
+///     Condition::eq("/sensors/2/state/buttonevent", 1002),
+///     Condition::dx("/sensors/2/state/lastupdated"),
+///     Condition::is_in("/config/localtime", "T18:00:00/T23:00:00"),
+/// ];
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Condition {
+    pub address: String,
+    pub operator: ConditionOperator,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+impl Condition {
+    fn new(address: &str, operator: ConditionOperator, value: Option<String>) -> Self {
+        Self {
+            address: address.to_owned(),
+            operator,
+            value,
+        }
+    }
+
+    pub fn eq(address: &str, value: impl ToString) -> Self {
+        Self::new(address, ConditionOperator::Eq, Some(value.to_string()))
+    }
+
+    pub fn gt(address: &str, value: impl ToString) -> Self {
+        Self::new(address, ConditionOperator::Gt, Some(value.to_string()))
+    }
+
+    pub fn lt(address: &str, value: impl ToString) -> Self {
+        Self::new(address, ConditionOperator::Lt, Some(value.to_string()))
+    }
+
+    /// Triggers when the value at `address` changed.
+    pub fn dx(address: &str) -> Self {
+        Self::new(address, ConditionOperator::Dx, None)
+    }
+
+    /// Triggers when the value at `address` changed, delayed by `delay` (e.g. `PT00:05:00`).
+    pub fn ddx(address: &str, delay: &str) -> Self {
+        Self::new(address, ConditionOperator::Ddx, Some(delay.to_owned()))
+    }
+
+    /// Value at `address` hasn't changed for `duration` (e.g. `PT00:05:00`).
+    pub fn stable(address: &str, duration: &str) -> Self {
+        Self::new(
+            address,
+            ConditionOperator::Stable,
+            Some(duration.to_owned()),
+        )
+    }
+
+    /// Value at `address` has changed within `duration` (e.g. `PT00:05:00`).
+    pub fn not_stable(address: &str, duration: &str) -> Self {
+        Self::new(
+            address,
+            ConditionOperator::NotStable,
+            Some(duration.to_owned()),
+        )
+    }
+
+    /// Time at `address` (usually `/config/localtime`) is within `range`, e.g. `T08:00:00/T18:00:00`.
+    pub fn is_in(address: &str, range: &str) -> Self {
+        Self::new(address, ConditionOperator::In, Some(range.to_owned()))
+    }
+
+    /// Time at `address` (usually `/config/localtime`) is outside `range`, e.g. `T08:00:00/T18:00:00`.
+    pub fn not_in(address: &str, range: &str) -> Self {
+        Self::new(address, ConditionOperator::NotIn, Some(range.to_owned()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConditionOperator {
+    #[serde(rename = "eq")]
+    Eq,
+    #[serde(rename = "gt")]
+    Gt,
+    #[serde(rename = "lt")]
+    Lt,
+    #[serde(rename = "dx")]
+    Dx,
+    #[serde(rename = "ddx")]
+    Ddx,
+    #[serde(rename = "stable")]
+    Stable,
+    #[serde(rename = "not stable")]
+    NotStable,
+    #[serde(rename = "in")]
+    In,
+    #[serde(rename = "not in")]
+    NotIn,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleStatus {
+    Enabled,
+    Disabled,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rule {
+    #[serde(skip)]
+    id: String,
+    name: String,
+    owner: Option<String>,
+    created: Option<String>,
+    #[serde(rename = "lasttriggered")]
+    last_triggered: Option<String>,
+    #[serde(rename = "timestriggered", default)]
+    times_triggered: u32,
+    status: RuleStatus,
+    #[serde(default)]
+    recycle: bool,
+    conditions: Vec<Condition>,
+    actions: Vec<Action>,
+}
+
+impl Rule {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// App key of the application which created this rule.
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    pub fn created(&self) -> Option<&str> {
+        self.created.as_deref()
+    }
+
+    /// `none` if the rule never triggered.
+    pub fn last_triggered(&self) -> Option<&str> {
+        self.last_triggered.as_deref()
+    }
+
+    pub fn times_triggered(&self) -> u32 {
+        self.times_triggered
+    }
+
+    pub fn status(&self) -> RuleStatus {
+        self.status
+    }
+
+    /// Whether the bridge deletes this rule once the resources it references are gone.
+    pub fn recycle(&self) -> bool {
+        self.recycle
+    }
+
+    pub fn conditions(&self) -> &[Condition] {
+        &self.conditions
+    }
+
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NewRule {
+    name: String,
+    status: RuleStatus,
+    recycle: bool,
+    conditions: Vec<Condition>,
+    actions: Vec<Action>,
+}
+
+impl NewRule {
+    /// A rule needs at least one condition and one action, the bridge allows up to 8 of each.
+    pub fn new(name: &str, conditions: Vec<Condition>, actions: Vec<Action>) -> Self {
+        Self {
+            name: name.to_owned(),
+            status: RuleStatus::Enabled,
+            recycle: false,
+            conditions,
+            actions,
+        }
+    }
+
+    /// Rules are enabled by default.
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.status = if enabled {
+            RuleStatus::Enabled
+        } else {
+            RuleStatus::Disabled
+        };
+        self
+    }
+
+    pub fn with_recycle(mut self, recycle: bool) -> Self {
+        self.recycle = recycle;
+        self
+    }
+}
+
+impl From<&Rule> for NewRule {
+    fn from(rule: &Rule) -> Self {
+        Self {
+            name: rule.name.clone(),
+            // the other statuses are set by the bridge, it only accepts these two
+            status: match rule.status {
+                RuleStatus::Enabled => RuleStatus::Enabled,
+                _ => RuleStatus::Disabled,
+            },
+            recycle: rule.recycle,
+            conditions: rule.conditions.clone(),
+            actions: rule.actions.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct StatusChange {
+    status: RuleStatus,
+}
+
+impl Bridge {
+    pub async fn rules(&self) -> Result<Vec<Rule>, Error> {
+        Ok(self
+            .v1_list::<Rule>("/rules")
+            .await?
+            .into_iter()
+            .map(|(id, rule)| Rule { id, ..rule })
+            .collect())
+    }
+
+    /// Creates a new rule and returns its id.
+    pub async fn create_rule(&self, rule: &NewRule) -> Result<String, Error> {
+        self.v1_create("/rules", rule).await
+    }
+
+    /// Replaces the rule with `id` completely.
+    pub async fn update_rule(&self, id: &str, rule: &NewRule) -> Result<(), Error> {
+        let path = format!("/rules/{}", id);
+        self.v1_send(Method::PUT, &path, Some(rule)).await?;
+        Ok(())
+    }
+
+    pub async fn set_rule_enabled(&self, id: &str, enabled: bool) -> Result<(), Error> {
+        let status = if enabled {
+            RuleStatus::Enabled
+        } else {
+            RuleStatus::Disabled
+        };
+        let path = format!("/rules/{}", id);
+        self.v1_send(Method::PUT, &path, Some(&StatusChange { status }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_rule(&self, id: &str) -> Result<(), Error> {
+        let path = format!("/rules/{}", id);
+        self.v1_send::<()>(Method::DELETE, &path, None).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(status: &str) -> Rule {
+        serde_json::from_value(json!({
+            "name": "switch on",
+            "owner": "oldkey",
+            "status": status,
+            "recycle": false,
+            "conditions": [
+                {"address": "/sensors/2/state/buttonevent", "operator": "eq", "value": "1002"}
+            ],
+            "actions": [
+                {"address": "/groups/0/action", "method": "PUT", "body": {"on": true}}
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn copy_keeps_enabled() {
+        let new = NewRule::from(&rule("enabled"));
+        assert_eq!(new.status, RuleStatus::Enabled);
+        assert_eq!(new.conditions.len(), 1);
+        assert_eq!(new.actions.len(), 1);
+    }
+
+    #[test]
+    fn copy_only_sends_enabled_or_disabled() {
+        for status in ["disabled", "resourcedeleted", "somethingnew"] {
+            let new = NewRule::from(&rule(status));
+            assert_eq!(new.status, RuleStatus::Disabled);
+            assert_eq!(serde_json::to_value(&new).unwrap()["status"], "disabled");
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::error::Error;
+use crate::resources::{Bridge, ResourceIdentifier};
+use reqwest::Method;
+use serde::Deserialize;
+
+/// Scene as set up in the Hue app (CLIP v2 `scene`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct Scene {
+    id: String,
+    metadata: SceneMetadata,
+    group: ResourceIdentifier,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SceneMetadata {
+    name: String,
+}
+
+impl Scene {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.metadata.name
+    }
+
+    /// The room or zone this scene belongs to.
+    pub fn group(&self) -> &ResourceIdentifier {
+        &self.group
+    }
+}
+
+impl Bridge {
+    pub async fn scenes(&self) -> Result<Vec<Scene>, Error> {
+        self.v2_get("scene").await
+    }
+
+    /// Activates the scene in its room or zone.
+    pub async fn recall_scene(&self, id: &str) -> Result<(), Error> {
+        let path = format!("/scene/{}", id);
+        let body = serde_json::json!({ "recall": { "action": "active" } });
+        self.v2_send(Method::PUT, &path, Some(&body)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_scenes() {
+        let scene = serde_json::from_value::<Scene>(json!({
+            "id": "7b3e1d94",
+            "type": "scene",
+            "metadata": { "name": "Concentrate", "image": { "rid": "x", "rtype": "public_image" } },
+            "group": { "rid": "5c2d3a10", "rtype": "room" },
+            "actions": [],
+            "speed": 0.6,
+        }))
+        .unwrap();
+        assert_eq!(scene.id(), "7b3e1d94");
+        assert_eq!(scene.name(), "Concentrate");
+        assert_eq!(scene.group().rid, "5c2d3a10");
+        assert_eq!(scene.group().rtype, "room");
+    }
+}
@@ -0,0 +1,259 @@
+use crate::error::Error;
+use crate::resources::{Action, Bridge};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleStatus {
+    Enabled,
+    Disabled,
+    /// The resource the command targets no longer exists.
+    Resourcedeleted,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Schedule {
+    #[serde(skip)]
+    id: String,
+    name: String,
+    #[serde(default)]
+    description: String,
+    command: Action,
+    #[serde(rename = "localtime")]
+    local_time: Option<String>,
+    status: ScheduleStatus,
+    #[serde(rename = "autodelete", default)]
+    auto_delete: bool,
+    #[serde(default)]
+    recycle: bool,
+    created: Option<String>,
+}
+
+impl Schedule {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The address of the command includes the app key it runs with, e.g.
+    /// `/api/<app key>/groups/0/action`.
+    pub fn command(&self) -> &Action {
+        &self.command
+    }
+
+    /// See [`NewSchedule::new`] for the supported formats.
+    pub fn local_time(&self) -> Option<&str> {
+        self.local_time.as_deref()
+    }
+
+    pub fn status(&self) -> ScheduleStatus {
+        self.status
+    }
+
+    /// Whether the bridge removes this schedule after it triggered once.
+    pub fn auto_delete(&self) -> bool {
+        self.auto_delete
+    }
+
+    pub fn recycle(&self) -> bool {
+        self.recycle
+    }
+
+    pub fn created(&self) -> Option<&str> {
+        self.created.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NewSchedule {
+    name: String,
+    description: String,
+    command: Action,
+    #[serde(rename = "localtime")]
+    local_time: String,
+    status: ScheduleStatus,
+    #[serde(rename = "autodelete", skip_serializing_if = "Option::is_none")]
+    auto_delete: Option<bool>,
+    recycle: bool,
+}
+
+impl NewSchedule {
+    /// Schedules `command` at `local_time` which is one of:
+    ///
+    /// | Format                    | Meaning                                           |
+    /// |---------------------------|---------------------------------------------------|
+    /// | `2022-06-21T07:00:00`     | Once, at the given date and time                  |
+    /// | `W124/T07:00:00`          | Weekly, on the days in the bitmask (0MTWTFSS)     |
+    /// | `PT00:10:00`              | Timer, once after the given duration              |
+    /// | `R05/PT00:10:00`          | Recurring timer, 5 times (`R` for forever)        |
+    ///
+    /// Like the actions of a rule the address of `command` is relative to the app key, e.g.
+    /// `/groups/0/action`. The bridge wants the full `/api/<app key>/groups/0/action`, the
+    /// app key of the bridge is added when the schedule is created.
+    pub fn new(name: &str, local_time: &str, command: Action) -> Self {
+        Self {
+            name: name.to_owned(),
+            description: String::new(),
+            command,
+            local_time: local_time.to_owned(),
+            status: ScheduleStatus::Enabled,
+            auto_delete: None,
+            recycle: false,
+        }
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_owned();
+        self
+    }
+
+    /// Schedules are enabled by default.
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.status = if enabled {
+            ScheduleStatus::Enabled
+        } else {
+            ScheduleStatus::Disabled
+        };
+        self
+    }
+
+    /// Only applies to non-recurring schedules, the bridge defaults to `true`.
+    pub fn with_auto_delete(mut self, auto_delete: bool) -> Self {
+        self.auto_delete = Some(auto_delete);
+        self
+    }
+
+    pub fn with_recycle(mut self, recycle: bool) -> Self {
+        self.recycle = recycle;
+        self
+    }
+}
+
+impl From<&Schedule> for NewSchedule {
+    fn from(schedule: &Schedule) -> Self {
+        Self {
+            name: schedule.name.clone(),
+            description: schedule.description.clone(),
+            command: Action {
+                address: relative_address(&schedule.command.address).to_owned(),
+                ..schedule.command.clone()
+            },
+            local_time: schedule.local_time.clone().unwrap_or_default(),
+            // the other statuses are set by the bridge, it only accepts these two
+            status: match schedule.status {
+                ScheduleStatus::Enabled => ScheduleStatus::Enabled,
+                _ => ScheduleStatus::Disabled,
+            },
+            auto_delete: Some(schedule.auto_delete),
+            recycle: schedule.recycle,
+        }
+    }
+}
+
+// strips the `/api/<app key>` prefix from the address of a schedule's command
+pub(crate) fn relative_address(address: &str) -> &str {
+    address
+        .strip_prefix("/api/")
+        .and_then(|rest| rest.find('/').map(|i| &rest[i..]))
+        .unwrap_or(address)
+}
+
+#[derive(Serialize)]
+struct StatusChange {
+    status: ScheduleStatus,
+}
+
+impl Bridge {
+    pub async fn schedules(&self) -> Result<Vec<Schedule>, Error> {
+        Ok(self
+            .v1_list::<Schedule>("/schedules")
+            .await?
+            .into_iter()
+            .map(|(id, schedule)| Schedule { id, ..schedule })
+            .collect())
+    }
+
+    /// Creates a new schedule and returns its id.
+    pub async fn create_schedule(&self, schedule: &NewSchedule) -> Result<String, Error> {
+        let app_key = self.app_key().ok_or(Error::MissingAppKey)?;
+        let mut schedule = schedule.clone();
+        schedule.command.address = format!(
+            "/api/{}{}",
+            app_key,
+            relative_address(&schedule.command.address)
+        );
+        self.v1_create("/schedules", &schedule).await
+    }
+
+    pub async fn set_schedule_enabled(&self, id: &str, enabled: bool) -> Result<(), Error> {
+        let status = if enabled {
+            ScheduleStatus::Enabled
+        } else {
+            ScheduleStatus::Disabled
+        };
+        let path = format!("/schedules/{}", id);
+        self.v1_send(Method::PUT, &path, Some(&StatusChange { status }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_schedule(&self, id: &str) -> Result<(), Error> {
+        let path = format!("/schedules/{}", id);
+        self.v1_send::<()>(Method::DELETE, &path, None).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schedule(status: &str) -> Schedule {
+        serde_json::from_value(json!({
+            "name": "wake up",
+            "command": {
+                "address": "/api/oldkey/groups/0/action",
+                "method": "PUT",
+                "body": {"on": true}
+            },
+            "localtime": "W124/T07:00:00",
+            "status": status,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn relative_addresses() {
+        assert_eq!(
+            relative_address("/api/key/lights/3/state"),
+            "/lights/3/state"
+        );
+        assert_eq!(relative_address("/groups/0/action"), "/groups/0/action");
+    }
+
+    #[test]
+    fn copy_strips_the_app_key() {
+        let new = NewSchedule::from(&schedule("enabled"));
+        assert_eq!(new.command.address, "/groups/0/action");
+        assert_eq!(new.status, ScheduleStatus::Enabled);
+    }
+
+    #[test]
+    fn copy_only_sends_enabled_or_disabled() {
+        for status in ["disabled", "resourcedeleted", "somethingnew"] {
+            let new = NewSchedule::from(&schedule(status));
+            assert_eq!(new.status, ScheduleStatus::Disabled);
+        }
+    }
+}
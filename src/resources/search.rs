@@ -0,0 +1,299 @@
+use crate::error::Error;
+use crate::resources::Bridge;
+use futures::stream::{self, Stream};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+// a search takes about 40 seconds, polling every 2 seconds reports devices soon after they're
+// found without flooding the bridge with requests
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+// the bridge rejects searches for more serial numbers than this
+const MAX_SERIALS: usize = 10;
+
+/// Light or sensor found during the last search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewDevice {
+    id: String,
+    name: String,
+}
+
+impl NewDevice {
+    /// The v1 id of the light or sensor.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LastScan {
+    /// A search is currently running.
+    Active,
+    /// No search has been done since the bridge was powered on.
+    None,
+    /// Time the last search finished, e.g. `2022-06-21T13:31:33`.
+    Finished(String),
+}
+
+/// Result of the last (or currently running) search.
+#[derive(Debug, Clone)]
+pub struct NewDevices {
+    last_scan: LastScan,
+    devices: Vec<NewDevice>,
+}
+
+impl NewDevices {
+    pub fn last_scan(&self) -> &LastScan {
+        &self.last_scan
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.last_scan == LastScan::Active
+    }
+
+    pub fn devices(&self) -> &[NewDevice] {
+        &self.devices
+    }
+}
+
+impl IntoIterator for NewDevices {
+    type Item = NewDevice;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.devices.into_iter()
+    }
+}
+
+// `lastscan` lives right beside the found devices which are keyed by their id
+#[derive(Deserialize)]
+struct RawNewDevices {
+    #[serde(rename = "lastscan")]
+    last_scan: String,
+    #[serde(flatten)]
+    devices: HashMap<String, RawNewDevice>,
+}
+
+#[derive(Deserialize)]
+struct RawNewDevice {
+    name: String,
+}
+
+impl From<RawNewDevices> for NewDevices {
+    fn from(raw: RawNewDevices) -> Self {
+        let last_scan = match raw.last_scan.as_str() {
+            "active" => LastScan::Active,
+            "none" => LastScan::None,
+            _ => LastScan::Finished(raw.last_scan),
+        };
+        let mut devices = raw
+            .devices
+            .into_iter()
+            .map(|(id, device)| NewDevice {
+                id,
+                name: device.name,
+            })
+            .collect::<Vec<_>>();
+        // ids are numeric but sent as strings, sort them numerically so `10` comes after `9`
+        devices.sort_by(|a, b| (a.id.len(), &a.id).cmp(&(b.id.len(), &b.id)));
+
+        Self { last_scan, devices }
+    }
+}
+
+#[derive(Serialize)]
+struct SearchRequest<'a> {
+    #[serde(rename = "deviceid")]
+    serials: &'a [&'a str],
+}
+
+impl<'a> SearchRequest<'a> {
+    // without serials the body is left out entirely
+    fn new(serials: &'a [&'a str]) -> Result<Option<Self>, Error> {
+        match serials.len() {
+            0 => Ok(None),
+            n if n > MAX_SERIALS => Err(Error::TooManySerials(n)),
+            _ => Ok(Some(Self { serials })),
+        }
+    }
+}
+
+/// Kind of device to search for, lights and sensors are searched for separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchKind {
+    Lights,
+    Sensors,
+}
+
+impl SearchKind {
+    fn path(&self) -> &'static str {
+        match self {
+            SearchKind::Lights => "/lights",
+            SearchKind::Sensors => "/sensors",
+        }
+    }
+}
+
+struct SearchState<'a> {
+    bridge: &'a Bridge,
+    kind: SearchKind,
+    polls: u32,
+    done: bool,
+    seen: HashSet<String>,
+    found: VecDeque<NewDevice>,
+}
+
+impl Bridge {
+    /// Starts searching for new devices for about 40 seconds.
+    ///
+    /// Bulbs which are already paired to another bridge or were reset can be found by
+    /// passing their serial numbers (up to 10, printed on the bulb), pass an empty slice
+    /// to search for any device in pairing mode. Fails with [`Error::TooManySerials`] for more
+    /// than 10 serial numbers.
+    pub async fn start_search(&self, kind: SearchKind, serials: &[&str]) -> Result<(), Error> {
+        let body = SearchRequest::new(serials)?;
+        self.v1_send(Method::POST, kind.path(), body.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    /// Devices found during the last search.
+    pub async fn new_devices(&self, kind: SearchKind) -> Result<NewDevices, Error> {
+        let path = format!("{}/new", kind.path());
+        Ok(self.v1_get::<RawNewDevices>(&path).await?.into())
+    }
+
+    /// Starts a search and yields every new device as soon as the bridge finds it, the stream
+    /// ends when the search is over.
+    pub fn search<'a>(
+        &'a self,
+        kind: SearchKind,
+        serials: &'a [&'a str],
+    ) -> impl Stream<Item = Result<NewDevice, Error>> + Send + 'a {
+        let state = SearchState {
+            bridge: self,
+            kind,
+            polls: 0,
+            done: false,
+            seen: HashSet::new(),
+            found: VecDeque::new(),
+        };
+
+        stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some(device) = state.found.pop_front() {
+                    return Some((Ok(device), state));
+                }
+                if state.done {
+                    return None;
+                }
+
+                if state.polls == 0 {
+                    if let Err(e) = state.bridge.start_search(state.kind, serials).await {
+                        state.done = true;
+                        return Some((Err(e), state));
+                    }
+                } else {
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+
+                let new = match state.bridge.new_devices(state.kind).await {
+                    Ok(new) => new,
+                    Err(e) => {
+                        state.done = true;
+                        return Some((Err(e), state));
+                    }
+                };
+                // the bridge might not have flipped `lastscan` to active yet right after starting
+                state.polls += 1;
+                state.done = !new.is_active() && state.polls > 1;
+                for device in new {
+                    if state.seen.insert(device.id.clone()) {
+                        state.found.push_back(device);
+                    }
+                }
+            }
+        })
+    }
+
+    /// Steals bulbs in close proximity (about 30cm) of the bridge away from whichever bridge
+    /// or remote they are paired to.
+    pub async fn touchlink(&self) -> Result<(), Error> {
+        self.v1_send(Method::PUT, "/config", Some(&Touchlink { touchlink: true }))
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct Touchlink {
+    touchlink: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn new_devices(value: serde_json::Value) -> NewDevices {
+        serde_json::from_value::<RawNewDevices>(value)
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn parse_last_scan() {
+        let new = new_devices(json!({ "lastscan": "active" }));
+        assert!(new.is_active());
+        assert!(new.devices().is_empty());
+
+        let new = new_devices(json!({ "lastscan": "none" }));
+        assert_eq!(new.last_scan(), &LastScan::None);
+
+        let new = new_devices(json!({ "lastscan": "2022-06-21T13:31:33" }));
+        assert_eq!(
+            new.last_scan(),
+            &LastScan::Finished("2022-06-21T13:31:33".to_owned())
+        );
+        assert!(!new.is_active());
+    }
+
+    #[test]
+    fn parse_devices_in_id_order() {
+        let new = new_devices(json!({
+            "lastscan": "active",
+            "10": { "name": "Hue color lamp 3" },
+            "2": { "name": "Hue color lamp 1" },
+            "9": { "name": "Hue color lamp 2" },
+        }));
+        let ids = new.devices().iter().map(NewDevice::id).collect::<Vec<_>>();
+        assert_eq!(ids, ["2", "9", "10"]);
+        assert_eq!(new.devices()[0].name(), "Hue color lamp 1");
+    }
+
+    #[test]
+    fn search_request_serials() {
+        assert!(SearchRequest::new(&[]).unwrap().is_none());
+
+        let serials = ["A1B2C3"; MAX_SERIALS];
+        let request = SearchRequest::new(&serials).unwrap();
+        assert_eq!(
+            serde_json::to_value(request).unwrap()["deviceid"]
+                .as_array()
+                .unwrap()
+                .len(),
+            MAX_SERIALS
+        );
+
+        let serials = ["A1B2C3"; MAX_SERIALS + 1];
+        assert!(matches!(
+            SearchRequest::new(&serials),
+            Err(Error::TooManySerials(11))
+        ));
+    }
+}
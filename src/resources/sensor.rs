@@ -0,0 +1,234 @@
+use crate::error::Error;
+use crate::resources::Bridge;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+const CLIP_GENERIC_STATUS: &str = "CLIPGenericStatus";
+const CLIP_GENERIC_FLAG: &str = "CLIPGenericFlag";
+
+/// v1 sensor, either a physical one (motion sensors, switches, ...) or a CLIP sensor which
+/// only lives on the bridge and is commonly used to keep state between rules.
+///
+/// `state` and `config` differ per sensor type so they are kept as plain json.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Sensor {
+    #[serde(skip)]
+    id: String,
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(rename = "modelid")]
+    model_id: String,
+    #[serde(rename = "manufacturername")]
+    manufacturer_name: String,
+    #[serde(rename = "swversion")]
+    sw_version: String,
+    #[serde(rename = "uniqueid")]
+    unique_id: Option<String>,
+    state: serde_json::Value,
+    config: serde_json::Value,
+    #[serde(default)]
+    recycle: bool,
+}
+
+impl Sensor {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Sensor type, e.g. `ZLLPresence`, `ZLLSwitch` or `CLIPGenericFlag`.
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    pub fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    pub fn manufacturer_name(&self) -> &str {
+        &self.manufacturer_name
+    }
+
+    pub fn sw_version(&self) -> &str {
+        &self.sw_version
+    }
+
+    pub fn unique_id(&self) -> Option<&str> {
+        self.unique_id.as_deref()
+    }
+
+    pub fn state(&self) -> &serde_json::Value {
+        &self.state
+    }
+
+    pub fn config(&self) -> &serde_json::Value {
+        &self.config
+    }
+
+    pub fn recycle(&self) -> bool {
+        self.recycle
+    }
+
+    /// Whether this sensor was created by an application instead of being a physical device.
+    pub fn is_clip(&self) -> bool {
+        self.kind.starts_with("CLIP")
+    }
+
+    /// Current value of a `CLIPGenericStatus` sensor.
+    pub fn generic_status(&self) -> Option<i64> {
+        if self.kind != CLIP_GENERIC_STATUS {
+            return None;
+        }
+        self.state.get("status")?.as_i64()
+    }
+
+    /// Current value of a `CLIPGenericFlag` sensor.
+    pub fn generic_flag(&self) -> Option<bool> {
+        if self.kind != CLIP_GENERIC_FLAG {
+            return None;
+        }
+        self.state.get("flag")?.as_bool()
+    }
+
+    /// `none` if the state never changed.
+    pub fn last_updated(&self) -> Option<&str> {
+        self.state.get("lastupdated")?.as_str()
+    }
+}
+
+/// New CLIP sensor, physical sensors are added through a [search](crate::resources::SearchKind::Sensors).
+#[derive(Debug, Clone, Serialize)]
+pub struct NewSensor {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(rename = "modelid")]
+    model_id: String,
+    #[serde(rename = "manufacturername")]
+    manufacturer_name: String,
+    #[serde(rename = "swversion")]
+    sw_version: String,
+    #[serde(rename = "uniqueid")]
+    unique_id: String,
+    recycle: bool,
+}
+
+impl NewSensor {
+    /// `kind` is one of the CLIP sensor types, e.g. `CLIPPresence` or `CLIPGenericStatus`.
+    /// The `unique_id` identifies this sensor for your application.
+    pub fn new(name: &str, kind: &str, unique_id: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            kind: kind.to_owned(),
+            model_id: "lightrary".to_owned(),
+            manufacturer_name: "lightrary".to_owned(),
+            sw_version: env!("CARGO_PKG_VERSION").to_owned(),
+            unique_id: unique_id.to_owned(),
+            recycle: false,
+        }
+    }
+
+    /// Sensor holding an integer, useful for state machines spanning multiple rules.
+    pub fn generic_status(name: &str, unique_id: &str) -> Self {
+        Self::new(name, CLIP_GENERIC_STATUS, unique_id)
+    }
+
+    /// Sensor holding a boolean.
+    pub fn generic_flag(name: &str, unique_id: &str) -> Self {
+        Self::new(name, CLIP_GENERIC_FLAG, unique_id)
+    }
+
+    pub fn with_model_id(mut self, model_id: &str) -> Self {
+        self.model_id = model_id.to_owned();
+        self
+    }
+
+    pub fn with_manufacturer_name(mut self, manufacturer_name: &str) -> Self {
+        self.manufacturer_name = manufacturer_name.to_owned();
+        self
+    }
+
+    pub fn with_recycle(mut self, recycle: bool) -> Self {
+        self.recycle = recycle;
+        self
+    }
+}
+
+impl From<&Sensor> for NewSensor {
+    fn from(sensor: &Sensor) -> Self {
+        Self {
+            name: sensor.name.clone(),
+            kind: sensor.kind.clone(),
+            model_id: sensor.model_id.clone(),
+            manufacturer_name: sensor.manufacturer_name.clone(),
+            sw_version: sensor.sw_version.clone(),
+            unique_id: sensor.unique_id.clone().unwrap_or_default(),
+            recycle: sensor.recycle,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct NameChange<'a> {
+    name: &'a str,
+}
+
+impl Bridge {
+    pub async fn sensors(&self) -> Result<Vec<Sensor>, Error> {
+        Ok(self
+            .v1_list::<Sensor>("/sensors")
+            .await?
+            .into_iter()
+            .map(|(id, sensor)| Sensor { id, ..sensor })
+            .collect())
+    }
+
+    /// Creates a new CLIP sensor and returns its id.
+    pub async fn create_sensor(&self, sensor: &NewSensor) -> Result<String, Error> {
+        self.v1_create("/sensors", sensor).await
+    }
+
+    pub async fn rename_sensor(&self, id: &str, name: &str) -> Result<(), Error> {
+        let path = format!("/sensors/{}", id);
+        self.v1_send(Method::PUT, &path, Some(&NameChange { name }))
+            .await?;
+        Ok(())
+    }
+
+    /// Changes the state of a sensor, only CLIP sensors accept state changes.
+    pub async fn set_sensor_state(&self, id: &str, state: &serde_json::Value) -> Result<(), Error> {
+        let path = format!("/sensors/{}/state", id);
+        self.v1_send(Method::PUT, &path, Some(state)).await?;
+        Ok(())
+    }
+
+    pub async fn set_sensor_config(
+        &self,
+        id: &str,
+        config: &serde_json::Value,
+    ) -> Result<(), Error> {
+        let path = format!("/sensors/{}/config", id);
+        self.v1_send(Method::PUT, &path, Some(config)).await?;
+        Ok(())
+    }
+
+    pub async fn set_generic_status(&self, id: &str, status: i64) -> Result<(), Error> {
+        self.set_sensor_state(id, &serde_json::json!({ "status": status }))
+            .await
+    }
+
+    pub async fn set_generic_flag(&self, id: &str, flag: bool) -> Result<(), Error> {
+        self.set_sensor_state(id, &serde_json::json!({ "flag": flag }))
+            .await
+    }
+
+    pub async fn delete_sensor(&self, id: &str) -> Result<(), Error> {
+        let path = format!("/sensors/{}", id);
+        self.v1_send::<()>(Method::DELETE, &path, None).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+use crate::error::Error;
+use crate::resources::{Bridge, ResourceIdentifier};
+#[cfg(feature = "eventstream")]
+use futures::stream::{self, Stream, StreamExt};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// Software update state of the bridge and all its devices, the `swupdate2` section of
+/// the bridge config.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SoftwareUpdate {
+    state: UpdateState,
+    #[serde(rename = "checkforupdate")]
+    checking: bool,
+    #[serde(rename = "lastchange")]
+    last_change: Option<String>,
+    bridge: BridgeUpdate,
+    #[serde(rename = "autoinstall")]
+    auto_install: AutoInstall,
+}
+
+impl SoftwareUpdate {
+    /// Combined state of all devices connected to the bridge.
+    pub fn state(&self) -> UpdateState {
+        self.state
+    }
+
+    pub fn bridge_state(&self) -> UpdateState {
+        self.bridge.state
+    }
+
+    /// Whether the bridge is currently checking for updates.
+    pub fn checking(&self) -> bool {
+        self.checking
+    }
+
+    pub fn last_change(&self) -> Option<&str> {
+        self.last_change.as_deref()
+    }
+
+    pub fn bridge_last_install(&self) -> Option<&str> {
+        self.bridge.last_install.as_deref()
+    }
+
+    pub fn auto_install(&self) -> &AutoInstall {
+        &self.auto_install
+    }
+
+    /// Whether anything is downloaded and ready to be installed.
+    pub fn ready_to_install(&self) -> bool {
+        matches!(
+            self.state,
+            UpdateState::AnyReadyToInstall | UpdateState::AllReadyToInstall
+        ) || self.bridge.state == UpdateState::ReadyToInstall
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct BridgeUpdate {
+    state: UpdateState,
+    #[serde(rename = "lastinstall")]
+    last_install: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateState {
+    NoUpdates,
+    Transferring,
+    ReadyToInstall,
+    AnyReadyToInstall,
+    AllReadyToInstall,
+    Installing,
+    #[serde(other)]
+    Unknown,
+}
+
+/// Automatic installation of updates, `update_time` is the local time of day in the
+/// format `T03:00:00`, installation starts somewhere within the hour after.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoInstall {
+    pub on: bool,
+    #[serde(rename = "updatetime", skip_serializing_if = "Option::is_none")]
+    pub update_time: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SoftwareUpdateConfig {
+    swupdate2: SoftwareUpdate,
+}
+
+#[derive(Serialize)]
+struct SoftwareUpdateChange<'a> {
+    swupdate2: SoftwareUpdateCommand<'a>,
+}
+
+#[derive(Default, Serialize)]
+struct SoftwareUpdateCommand<'a> {
+    #[serde(rename = "checkforupdate", skip_serializing_if = "Option::is_none")]
+    check_for_update: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    install: Option<bool>,
+    #[serde(rename = "autoinstall", skip_serializing_if = "Option::is_none")]
+    auto_install: Option<&'a AutoInstall>,
+}
+
+/// Software update state of a single device (CLIP v2 `device_software_update`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceSoftwareUpdate {
+    id: String,
+    owner: ResourceIdentifier,
+    state: DeviceUpdateState,
+    #[serde(default)]
+    problems: Vec<String>,
+}
+
+impl DeviceSoftwareUpdate {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The device this update belongs to.
+    pub fn owner(&self) -> &ResourceIdentifier {
+        &self.owner
+    }
+
+    pub fn state(&self) -> DeviceUpdateState {
+        self.state
+    }
+
+    pub fn problems(&self) -> &[String] {
+        &self.problems
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.state != DeviceUpdateState::NoUpdate
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceUpdateState {
+    NoUpdate,
+    UpdatePending,
+    ReadyToInstall,
+    Installing,
+    #[serde(other)]
+    Unknown,
+}
+
+/// Change to a device's software update as pushed over the event stream.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceSoftwareUpdateEvent {
+    pub id: String,
+    pub owner: Option<ResourceIdentifier>,
+    pub state: Option<DeviceUpdateState>,
+    pub problems: Option<Vec<String>>,
+}
+
+impl Bridge {
+    pub async fn software_update(&self) -> Result<SoftwareUpdate, Error> {
+        Ok(self
+            .v1_get::<SoftwareUpdateConfig>("/config")
+            .await?
+            .swupdate2)
+    }
+
+    /// Tells the bridge to look for new updates for itself and all its devices. This
+    /// takes a while, [`SoftwareUpdate::checking`] is `true` until it's done.
+    pub async fn check_for_updates(&self) -> Result<(), Error> {
+        self.update_software(SoftwareUpdateCommand {
+            check_for_update: Some(true),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Installs all updates which are [ready to install](SoftwareUpdate::ready_to_install).
+    pub async fn install_updates(&self) -> Result<(), Error> {
+        self.update_software(SoftwareUpdateCommand {
+            install: Some(true),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Configures when the bridge installs updates on its own, e.g. outside of business hours.
+    pub async fn set_auto_install(&self, auto_install: &AutoInstall) -> Result<(), Error> {
+        self.update_software(SoftwareUpdateCommand {
+            auto_install: Some(auto_install),
+            ..Default::default()
+        })
+        .await
+    }
+
+    async fn update_software(&self, command: SoftwareUpdateCommand<'_>) -> Result<(), Error> {
+        let change = SoftwareUpdateChange { swupdate2: command };
+        self.v1_send(Method::PUT, "/config", Some(&change)).await?;
+        Ok(())
+    }
+
+    pub async fn device_software_updates(&self) -> Result<Vec<DeviceSoftwareUpdate>, Error> {
+        self.v2_get("device_software_update").await
+    }
+
+    /// Follows the progress of device updates through the event stream.
+    #[cfg(feature = "eventstream")]
+    pub fn device_software_update_events(
+        &self,
+    ) -> impl Stream<Item = Result<DeviceSoftwareUpdateEvent, Error>> + Send + '_ {
+        self.events().flat_map(|event| {
+            let updates = match event {
+                Ok(event) => event
+                    .resources("device_software_update")
+                    .collect::<Vec<_>>(),
+                Err(e) => vec![Err(e)],
+            };
+            stream::iter(updates)
+        })
+    }
+}
@@ -0,0 +1,161 @@
+use crate::discovery::{BoxDiscoverer, Discoverer, DiscoveryBroker};
+use crate::resources::bridge::fetch_config;
+use crate::resources::{Bridge, UnauthBridges};
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// What the [`Watchdog`] is doing, passed to its [`on_event`](Watchdog::on_event) hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchdogEvent {
+    /// Couldn't connect to the bridge, it's being rediscovered.
+    Unreachable { bridge_id: String, ip: Ipv4Addr },
+    /// The bridge was found at a new address, the failed request is sent again.
+    AddressChanged {
+        bridge_id: String,
+        old: Ipv4Addr,
+        new: Ipv4Addr,
+    },
+    /// None of the discovery protocols found the bridge, the connection error is returned.
+    NotFound { bridge_id: String },
+}
+
+type WatchdogHook = Arc<dyn Fn(&WatchdogEvent) + Send + Sync>;
+
+/// Follows a bridge to its new address when its IP changes, e.g. after the DHCP lease ran out.
+///
+/// When a request fails because the bridge can't be connected to, the watchdog runs its
+/// discovery protocols in order, looks for the bridge with the same
+/// [bridge id](crate::resources::BridgeConfig::bridge_id) and sends the request again. By
+/// default mDNS is tried first, followed by the discovery endpoint.
+///
+/// ```no_run
+/// use lightrary::resources::{Bridge, Watchdog, WatchdogEvent};
+///
+/// # fn run(bridge: Bridge) {
+/// let bridge = bridge.with_watchdog(Watchdog::default().on_event(|event| {
+///     if let WatchdogEvent::AddressChanged { bridge_id, old, new } = event {
+///         eprintln!("bridge {} moved from {} to {}", bridge_id, old, new);
+///     }
+/// }));
+/// # }
+/// ```
+///
+/// Clones share their state, so one watchdog can (and should) be shared between bridges.
+#[derive(Clone)]
+pub struct Watchdog {
+    discoverers: Vec<BoxDiscoverer>,
+    on_event: Option<WatchdogHook>,
+    // only one rediscovery at a time, requests failing meanwhile wait for its outcome
+    relocating: Arc<Mutex<()>>,
+}
+
+impl fmt::Debug for Watchdog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watchdog")
+            .field("discoverers", &self.discoverers.len())
+            .field("on_event", &self.on_event.is_some())
+            .finish()
+    }
+}
+
+/// Rediscovers through mDNS first, followed by the discovery endpoint. Without the `mdns` and
+/// `cloud-discovery` features SSDP is used instead.
+impl Default for Watchdog {
+    fn default() -> Self {
+        let watchdog = Self::new();
+        #[cfg(feature = "mdns")]
+        let watchdog = watchdog.with_discovery(DiscoveryBroker::mdns());
+        #[cfg(feature = "cloud-discovery")]
+        let watchdog = watchdog.with_discovery(DiscoveryBroker::discovery_endpoint());
+        #[cfg(not(any(feature = "mdns", feature = "cloud-discovery")))]
+        let watchdog = watchdog.with_discovery(DiscoveryBroker::ssdp());
+        watchdog
+    }
+}
+
+impl Watchdog {
+    /// Creates a watchdog without any discovery protocols, add them with
+    /// [`with_discovery`](Self::with_discovery).
+    pub fn new() -> Self {
+        Self {
+            discoverers: Vec::new(),
+            on_event: None,
+            relocating: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Adds a discovery protocol, tried in the order they were added.
+    pub fn with_discovery<D>(mut self, broker: DiscoveryBroker<D>) -> Self
+    where
+        D: Discoverer + Send + Sync + 'static,
+        D::Device: Into<UnauthBridges>,
+    {
+        self.discoverers.push(broker.boxed());
+        self
+    }
+
+    /// Called on every [`WatchdogEvent`], e.g. to log them.
+    pub fn on_event<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&WatchdogEvent) + Send + Sync + 'static,
+    {
+        self.on_event = Some(Arc::new(f));
+        self
+    }
+
+    /// Looks for `bridge` after it couldn't be reached at `failed`, returns whether the
+    /// request is worth sending again.
+    pub(crate) async fn relocate(&self, bridge: &Bridge, failed: Ipv4Addr) -> bool {
+        let _relocating = self.relocating.lock().await;
+        // another request already moved the bridge while we were waiting
+        if bridge.ip() != failed {
+            return true;
+        }
+
+        let bridge_id = bridge.config.bridgeid.clone();
+        self.notify(&WatchdogEvent::Unreachable {
+            bridge_id: bridge_id.clone(),
+            ip: failed,
+        });
+        for discover in &self.discoverers {
+            // a failing protocol (no internet, no multicast) shouldn't stop the others
+            let candidates = match discover().await {
+                Ok(candidates) => candidates,
+                Err(_) => continue,
+            };
+            for candidate in candidates {
+                if candidate.ip == failed
+                    || candidate
+                        .id
+                        .as_deref()
+                        .is_some_and(|id| !id.eq_ignore_ascii_case(&bridge_id))
+                {
+                    continue;
+                }
+                let found = fetch_config(&bridge.session, candidate.addr())
+                    .await
+                    .is_ok_and(|config| config.bridgeid.eq_ignore_ascii_case(&bridge_id));
+                if found {
+                    *bridge.ip.write().unwrap() = candidate.ip;
+                    self.notify(&WatchdogEvent::AddressChanged {
+                        bridge_id,
+                        old: failed,
+                        new: candidate.ip,
+                    });
+                    return true;
+                }
+            }
+        }
+
+        self.notify(&WatchdogEvent::NotFound { bridge_id });
+        false
+    }
+
+    fn notify(&self, event: &WatchdogEvent) {
+        if let Some(on_event) = &self.on_event {
+            on_event(event);
+        }
+    }
+}
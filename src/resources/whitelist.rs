@@ -0,0 +1,115 @@
+use crate::error::Error;
+use crate::resources::Bridge;
+use reqwest::Method;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Application (app key) registered on the bridge through [`Bridge::gen_key`](crate::resources::Bridge::gen_key).
+///
+/// Dates are kept exactly as the bridge reports them, in UTC: `2022-06-21T13:31:33`.
+#[derive(Debug, Clone)]
+pub struct Application {
+    key: String,
+    name: String,
+    create_date: String,
+    last_use_date: String,
+}
+
+impl Application {
+    /// The app key, this is what you pass to [`Bridge::with_key`](crate::resources::Bridge::with_key).
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Full device type as registered: `app_name#instance_name`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn app_name(&self) -> &str {
+        self.name.split_once('#').map_or(&self.name, |(app, _)| app)
+    }
+
+    pub fn instance_name(&self) -> Option<&str> {
+        self.name.split_once('#').map(|(_, instance)| instance)
+    }
+
+    pub fn create_date(&self) -> &str {
+        &self.create_date
+    }
+
+    pub fn last_use_date(&self) -> &str {
+        &self.last_use_date
+    }
+}
+
+#[derive(Debug)]
+pub struct Applications(pub(crate) Vec<Application>);
+
+impl Applications {
+    pub fn iter(&self) -> std::slice::Iter<'_, Application> {
+        self.0.iter()
+    }
+
+    /// All applications registered under `app_name`, regardless of their instance name.
+    pub fn app_name<'a>(&'a self, app_name: &'a str) -> impl Iterator<Item = &'a Application> {
+        self.0.iter().filter(move |app| app.app_name() == app_name)
+    }
+
+    pub fn key(&self, key: &str) -> Option<&Application> {
+        self.0.iter().find(|app| app.key == key)
+    }
+}
+
+impl IntoIterator for Applications {
+    type Item = Application;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[derive(Deserialize)]
+struct WhitelistConfig {
+    whitelist: HashMap<String, WhitelistEntry>,
+}
+
+#[derive(Deserialize)]
+struct WhitelistEntry {
+    name: String,
+    #[serde(rename = "create date")]
+    create_date: String,
+    #[serde(rename = "last use date")]
+    last_use_date: String,
+}
+
+impl Bridge {
+    /// Lists every application that has been registered on this bridge.
+    pub async fn applications(&self) -> Result<Applications, Error> {
+        let config = self.v1_get::<WhitelistConfig>("/config").await?;
+        let mut apps = config
+            .whitelist
+            .into_iter()
+            .map(|(key, entry)| Application {
+                key,
+                name: entry.name,
+                create_date: entry.create_date,
+                last_use_date: entry.last_use_date,
+            })
+            .collect::<Vec<_>>();
+        apps.sort_by(|a, b| a.create_date.cmp(&b.create_date));
+
+        Ok(Applications(apps))
+    }
+
+    /// Revokes an app key so it can no longer be used to control the bridge.
+    ///
+    /// Note: recent bridge firmware only allows this through the Hue account website,
+    /// the bridge will answer with an [`ApiError`](crate::error::ApiError) in that case.
+    pub async fn delete_application(&self, key: &str) -> Result<(), Error> {
+        self.v1_send::<()>(Method::DELETE, &format!("/config/whitelist/{}", key), None)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,354 @@
+//! Retrying requests which failed because of a transient problem.
+//!
+//! Bridges regularly answer with `503 Service Unavailable` when they're busy, or `429 Too
+//! Many Requests` when they're being rate limited, and commands routed over zigbee can time
+//! out. The v1 api reports a busy device as a `200 OK` with an error in the body instead,
+//! e.g. `[{"error": {"type": 901, ...}}]`. Every request a [`Session`](crate::session::Session) sends goes through its
+//! [`RetryPolicy`] which retries those with an exponential backoff.
+//!
+//! Only idempotent requests (`GET`, `PUT`, `DELETE`, ...) are retried by default, retrying a
+//! `POST` might create the same resource twice.
+//!
+//! ```no_run
+//! use lightrary::retry::RetryPolicy;
+//! use lightrary::session::Session;
+//! use std::time::Duration;
+//!
+//! # fn main() -> Result<(), lightrary::error::Error> {
+//! let policy = RetryPolicy::default()
+//!     .with_max_attempts(5)
+//!     .with_initial_backoff(Duration::from_millis(200))
+//!     .on_retry(|event| eprintln!("retrying {} after {:?}: {:?}", event.method, event.delay, event.reason));
+//! let session = Session::builder().retry_policy(policy).build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::transport::{Method, Response, StatusCode};
+use http::header::CONTENT_TYPE;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Why a request is being retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryReason {
+    /// The bridge responded with one of the [retryable statuses](RetryPolicy::with_statuses).
+    Status(StatusCode),
+    /// Couldn't connect to the bridge.
+    Connect,
+    /// The request timed out.
+    Timeout,
+    /// The v1 api responded with one of the [retryable error types](RetryPolicy::with_api_errors).
+    Api(i32),
+}
+
+/// Passed to the [`on_retry`](RetryPolicy::on_retry) hook right before waiting for the next attempt.
+#[derive(Debug, Clone)]
+pub struct RetryEvent {
+    pub method: Method,
+    /// The attempt that failed, starting at 1.
+    pub attempt: u32,
+    pub reason: RetryReason,
+    /// How long until the next attempt.
+    pub delay: Duration,
+}
+
+type RetryHook = Arc<dyn Fn(&RetryEvent) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    retry_non_idempotent: bool,
+    statuses: Vec<StatusCode>,
+    api_errors: Vec<i32>,
+    on_retry: Option<RetryHook>,
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("jitter", &self.jitter)
+            .field("retry_non_idempotent", &self.retry_non_idempotent)
+            .field("statuses", &self.statuses)
+            .field("api_errors", &self.api_errors)
+            .field("on_retry", &self.on_retry.is_some())
+            .finish()
+    }
+}
+
+/// 3 attempts in total, starting with a 100ms backoff up to 2s, with jitter.
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            jitter: true,
+            retry_non_idempotent: false,
+            statuses: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            api_errors: vec![901],
+            on_retry: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retries, every request is sent exactly once.
+    pub fn none() -> Self {
+        Self::default().with_max_attempts(1)
+    }
+
+    /// Total number of attempts, including the first one.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Backoff before the first retry, doubled for every retry after.
+    pub fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    pub fn with_max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Randomizes every backoff between half and the full duration so multiple clients don't
+    /// retry in lockstep, enabled by default.
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Also retries `POST` requests, which might create the same resource twice.
+    pub fn with_retry_non_idempotent(mut self, retry: bool) -> Self {
+        self.retry_non_idempotent = retry;
+        self
+    }
+
+    /// Response statuses which are retried, `429`, `502`, `503` and `504` by default.
+    pub fn with_statuses(mut self, statuses: &[StatusCode]) -> Self {
+        self.statuses = statuses.to_vec();
+        self
+    }
+
+    /// Error types of the v1 api which are retried, only `901` (internal error, mostly the
+    /// zigbee network being busy) by default. `201` (parameter not modifiable because the
+    /// device is off) usually doesn't go away by itself, it's only retried when added here.
+    pub fn with_api_errors(mut self, types: &[i32]) -> Self {
+        self.api_errors = types.to_vec();
+        self
+    }
+
+    /// Called before every retry, e.g. to log or count them.
+    pub fn on_retry<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&RetryEvent) + Send + Sync + 'static,
+    {
+        self.on_retry = Some(Arc::new(f));
+        self
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    pub(crate) fn retries(&self, method: &Method) -> bool {
+        self.retry_non_idempotent || method.is_idempotent()
+    }
+
+    /// Whether the body of `res` has to be read to tell if it's retried.
+    pub(crate) fn inspects(&self, res: &Response) -> bool {
+        let is_json = res
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/json"));
+        !self.api_errors.is_empty() && res.status().is_success() && is_json
+    }
+
+    pub(crate) fn reason(&self, res: &Result<Response, Error>) -> Option<RetryReason> {
+        match res {
+            Ok(res) if self.statuses.contains(&res.status()) => {
+                Some(RetryReason::Status(res.status()))
+            }
+            Ok(res) => res
+                .body()
+                .and_then(|body| self.api_error(body))
+                .map(RetryReason::Api),
+            Err(Error::Reqwest(e)) if e.is_connect() => Some(RetryReason::Connect),
+            Err(Error::Reqwest(e)) if e.is_timeout() => Some(RetryReason::Timeout),
+            // failures of custom transports are theirs to retry
+            Err(_) => None,
+        }
+    }
+
+    // the v1 api responds with a list of results, any of which might be an error
+    fn api_error(&self, body: &[u8]) -> Option<i32> {
+        serde_json::from_slice::<Vec<serde_json::Value>>(body)
+            .ok()?
+            .iter()
+            .filter_map(|result| result.get("error")?.get("type")?.as_i64())
+            .filter_map(|t| i32::try_from(t).ok())
+            .find(|t| self.api_errors.contains(t))
+    }
+
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff);
+        if self.jitter {
+            backoff / 2 + backoff.mul_f64(random_fraction() / 2.0)
+        } else {
+            backoff
+        }
+    }
+
+    pub(crate) fn notify(&self, event: &RetryEvent) {
+        if let Some(on_retry) = &self.on_retry {
+            on_retry(event);
+        }
+    }
+}
+
+// jitter doesn't need a proper rng, the randomly seeded std hasher is good enough
+fn random_fraction() -> f64 {
+    let n = RandomState::new().build_hasher().finish();
+    (n % 10_000) as f64 / 10_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    fn json(status: StatusCode, body: &str) -> Response {
+        let mut headers = http::HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        Response::new(status, body).with_headers(headers)
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_max() {
+        let policy = RetryPolicy::default()
+            .with_jitter(false)
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_millis(500));
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(100), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn jitter_stays_between_half_and_the_full_backoff() {
+        let policy = RetryPolicy::default()
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_secs(10));
+        for attempt in 1..=5 {
+            let full = Duration::from_millis(100) * 2u32.pow(attempt - 1);
+            for _ in 0..100 {
+                let backoff = policy.backoff(attempt);
+                assert!(backoff >= full / 2 && backoff <= full, "{:?}", backoff);
+            }
+        }
+    }
+
+    #[test]
+    fn only_idempotent_methods_are_retried() {
+        let policy = RetryPolicy::default();
+        assert!(policy.retries(&Method::GET));
+        assert!(policy.retries(&Method::PUT));
+        assert!(policy.retries(&Method::DELETE));
+        assert!(!policy.retries(&Method::POST));
+
+        let policy = policy.with_retry_non_idempotent(true);
+        assert!(policy.retries(&Method::POST));
+    }
+
+    #[test]
+    fn at_least_one_attempt() {
+        assert_eq!(RetryPolicy::none().max_attempts(), 1);
+        assert_eq!(
+            RetryPolicy::default().with_max_attempts(0).max_attempts(),
+            1
+        );
+    }
+
+    #[test]
+    fn retryable_statuses() {
+        let policy = RetryPolicy::default();
+        let reason = |status| policy.reason(&Ok(Response::new(status, "")));
+        assert_eq!(
+            reason(StatusCode::SERVICE_UNAVAILABLE),
+            Some(RetryReason::Status(StatusCode::SERVICE_UNAVAILABLE))
+        );
+        assert_eq!(reason(StatusCode::NOT_FOUND), None);
+        assert_eq!(reason(StatusCode::OK), None);
+    }
+
+    #[test]
+    fn retryable_api_errors() {
+        let policy = RetryPolicy::default();
+        let busy = json(
+            StatusCode::OK,
+            r#"[{"success": {"/lights/1/state/on": true}},
+                {"error": {"type": 901, "address": "/lights/1/state/bri", "description": "internal error"}}]"#,
+        );
+        assert!(policy.inspects(&busy));
+        assert_eq!(policy.reason(&Ok(busy)), Some(RetryReason::Api(901)));
+
+        let unauthorized = json(
+            StatusCode::OK,
+            r#"[{"error": {"type": 1, "address": "/", "description": "unauthorized user"}}]"#,
+        );
+        assert_eq!(policy.reason(&Ok(unauthorized)), None);
+
+        let ok = json(StatusCode::OK, r#"{"name": "Hue"}"#);
+        assert_eq!(policy.reason(&Ok(ok)), None);
+    }
+
+    #[test]
+    fn configured_api_errors() {
+        let body = r#"[{"error": {"type": 201, "address": "/lights/1/state/bri", "description": "device is set to off"}}]"#;
+        // the light stays off, retrying only delays the same error
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.reason(&Ok(json(StatusCode::OK, body))), None);
+
+        let policy = RetryPolicy::default().with_api_errors(&[]);
+        assert!(!policy.inspects(&json(StatusCode::OK, body)));
+        assert_eq!(policy.reason(&Ok(json(StatusCode::OK, body))), None);
+
+        let policy = RetryPolicy::default().with_api_errors(&[201]);
+        assert_eq!(
+            policy.reason(&Ok(json(StatusCode::OK, body))),
+            Some(RetryReason::Api(201))
+        );
+    }
+
+    #[test]
+    fn streamed_bodies_are_left_alone() {
+        let policy = RetryPolicy::default();
+        let events = Response::new(StatusCode::OK, "data: []\n\n");
+        assert!(!policy.inspects(&events));
+    }
+}
@@ -1,36 +1,376 @@
-use crate::error::Error;
-use reqwest::Client;
+use crate::error::{Error, Redacted};
+use crate::retry::{RetryEvent, RetryPolicy};
+#[cfg(feature = "metrics")]
+use crate::telemetry;
+use crate::transport::{Request, Response, Transport};
+use reqwest::{Client, ClientBuilder, IntoUrl, Method, RequestBuilder, Url};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-// keeping this type private for the time being, until I figure out its place in the public
-// api and what to do with the `danger_accept_invalid_certs`
-#[derive(Debug)]
-pub(crate) struct Session {
+pub use reqwest::{Certificate, Proxy};
+
+/// HTTP session used to talk to bridges.
+///
+/// Cloning a session is cheap and clones share the same connection pool, so a single session
+/// can (and should) be shared between all your bridges, see [`UnauthBridge::auth_with`](crate::resources::UnauthBridge::auth_with).
+#[derive(Debug, Clone)]
+pub struct Session {
+    // builds the requests, which are sent through the transport
     inner: Client,
+    transport: Arc<dyn Transport>,
+    retry: RetryPolicy,
+    verifies_certs: bool,
 }
 
 impl Session {
+    /// Creates a session with the default configuration.
     pub fn new() -> Result<Self, Error> {
-        let client = Client::builder()
+        SessionBuilder::new().build()
+    }
+
+    pub fn builder() -> SessionBuilder {
+        SessionBuilder::new()
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    /// The same session (connection pool, transport and all) retrying requests differently.
+    pub(crate) fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Whether certificates are verified, a custom [`Transport`] is trusted to do so.
+    pub(crate) fn verifies_certs(&self) -> bool {
+        self.verifies_certs
+    }
+
+    pub(crate) fn request(&self, method: Method, url: impl IntoUrl) -> RequestBuilder {
+        self.inner.request(method, url)
+    }
+
+    pub(crate) fn get(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.request(Method::GET, url)
+    }
+
+    /// Sends a request, retrying it according to the [`RetryPolicy`] of this session.
+    pub(crate) async fn send(&self, req: RequestBuilder) -> Result<Response, Error> {
+        self.send_for(None, req).await
+    }
+
+    /// Like [`send`](Self::send), labeling logs and metrics with the bridge the request is for.
+    pub(crate) async fn send_for(
+        &self,
+        bridge_id: Option<&str>,
+        req: RequestBuilder,
+    ) -> Result<Response, Error> {
+        let bridge_id = bridge_id.unwrap_or("unknown");
+        let request = req.build()?;
+        let method = request.method().clone();
+        let host = request.url().host_str().unwrap_or_default().to_owned();
+        let path = redacted_path(request.url());
+        let retries = self.retry.retries(request.method());
+        let mut attempt = 1;
+        #[cfg(feature = "metrics")]
+        let _in_flight = telemetry::InFlight::new(bridge_id);
+
+        loop {
+            let start = Instant::now();
+            let res = match self.transport.execute(to_http(&request, &path)?).await {
+                Ok(res) if retries && self.retry.inspects(&res) => res.buffered().await,
+                res => res,
+            };
+            let latency = start.elapsed();
+            match &res {
+                Ok(res) => tracing::debug!(
+                    bridge_id, %method, %host, %path,
+                    status = res.status().as_u16(), ?latency, attempt,
+                    "request sent"
+                ),
+                Err(e) => tracing::debug!(
+                    bridge_id, %method, %host, %path,
+                    error = %Redacted(e), ?latency, attempt,
+                    "request failed"
+                ),
+            }
+            #[cfg(feature = "metrics")]
+            telemetry::request(bridge_id, &method, &res, latency);
+
+            match self.retry.reason(&res) {
+                Some(reason) if retries && attempt < self.retry.max_attempts() => {
+                    let delay = self.retry.backoff(attempt);
+                    tracing::warn!(
+                        bridge_id, %method, %host, %path,
+                        ?reason, ?delay, attempt,
+                        "retrying request"
+                    );
+                    #[cfg(feature = "metrics")]
+                    telemetry::retry(bridge_id, reason);
+                    self.retry.notify(&RetryEvent {
+                        method: method.clone(),
+                        attempt,
+                        reason,
+                        delay,
+                    });
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                _ => return res,
+            }
+        }
+    }
+}
+
+// reqwest's builder is handy to build requests with, transports get plain http requests
+fn to_http(request: &reqwest::Request, path: &str) -> Result<Request, Error> {
+    let body = request
+        .body()
+        .and_then(reqwest::Body::as_bytes)
+        .unwrap_or_default();
+    let mut http = http::Request::new(body.to_vec());
+    *http.method_mut() = request.method().clone();
+    *http.uri_mut() = request
+        .url()
+        .as_str()
+        .parse()
+        .map_err(|_| Error::InvalidUrl(path.to_owned()))?;
+    *http.headers_mut() = request.headers().clone();
+    Ok(http)
+}
+
+// the app key is part of the path of v1 requests (`/api/<app key>/lights`), it mustn't end
+// up in anyone's logs
+pub(crate) fn redacted_path(url: &Url) -> String {
+    let mut after_api = false;
+    let segments = url.path().split('/').map(|segment| {
+        let redact = after_api && !segment.is_empty() && segment != "0";
+        after_api = segment == "api";
+        match redact {
+            true => "<redacted>",
+            false => segment,
+        }
+    });
+    segments.collect::<Vec<_>>().join("/")
+}
+
+/// Configures the HTTP client of a [`Session`].
+///
+/// ```no_run
+/// use lightrary::session::Session;
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), lightrary::error::Error> {
+/// let session = Session::builder()
+///     .timeout(Duration::from_secs(5))
+///     .pool_max_idle_per_host(4)
+///     .user_agent("my-lighting-service/1.0")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SessionBuilder {
+    inner: ClientBuilder,
+    accept_invalid_certs: bool,
+    transport: Option<Arc<dyn Transport>>,
+    retry: RetryPolicy,
+}
+
+impl Default for SessionBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SessionBuilder {
+    pub fn new() -> Self {
+        let inner = Client::builder()
             .https_only(true)
-            // the resolver is pretty useless because when you connect through 
+            // the resolver is pretty useless because when you connect through
             // any other means than the discovery endpoint you won't have the bridge
             // id and I don't know any way to obtain one; perhaps file an issue with philips
             // .resolve(&bridge.id, format!("{}:443", bridge.ip).parse()?)
+            .user_agent(concat!("lightrary/", env!("CARGO_PKG_VERSION")));
+
+        Self {
+            inner,
+            accept_invalid_certs: true,
+            transport: None,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Timeout of a whole request, from connecting until the body has been read. Don't set
+    /// this on a session used for the event stream, it would cut off the stream.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.map(|b| b.timeout(timeout))
+    }
 
+    pub fn connect_timeout(self, timeout: Duration) -> Self {
+        self.map(|b| b.connect_timeout(timeout))
+    }
+
+    /// Maximum number of idle connections kept open per bridge.
+    pub fn pool_max_idle_per_host(self, max: usize) -> Self {
+        self.map(|b| b.pool_max_idle_per_host(max))
+    }
+
+    pub fn pool_idle_timeout(self, timeout: Option<Duration>) -> Self {
+        self.map(|b| b.pool_idle_timeout(timeout))
+    }
+
+    /// Only speak HTTP/2 to bridges, which multiplexes all requests over a single connection.
+    pub fn http2_prior_knowledge(self) -> Self {
+        self.map(|b| b.http2_prior_knowledge())
+    }
+
+    pub fn proxy(self, proxy: Proxy) -> Self {
+        self.map(|b| b.proxy(proxy))
+    }
+
+    /// Disables every proxy, including the ones from the environment.
+    pub fn no_proxy(self) -> Self {
+        self.map(|b| b.no_proxy())
+    }
+
+    /// Binds outgoing connections to a local address, e.g. to pick the interface on the
+    /// lighting network.
+    pub fn local_address(self, addr: IpAddr) -> Self {
+        self.map(|b| b.local_address(addr))
+    }
+
+    pub fn user_agent(self, user_agent: &str) -> Self {
+        let user_agent = user_agent.to_owned();
+        self.map(|b| b.user_agent(user_agent))
+    }
+
+    /// Trusts an additional root certificate, e.g. the Hue bridge root CA.
+    pub fn add_root_certificate(self, cert: Certificate) -> Self {
+        self.map(|b| b.add_root_certificate(cert))
+    }
+
+    /// Whether plain http is refused, `true` by default. Only turn this off to talk to a local
+    /// stand-in of the [remote api](crate::remote).
+    pub fn https_only(self, https_only: bool) -> Self {
+        self.map(|b| b.https_only(https_only))
+    }
+
+    /// Whether invalid certificates are accepted, `true` by default.
+    ///
+    /// Bridges use self-signed certificates with the bridge id as common name, which doesn't
+    /// verify when connecting by IP. Only turn this off when you've set up the bridge's root
+    /// certificate and a way to resolve bridges by their id, or for the session of the
+    /// [remote api](crate::remote) which refuses to send credentials without it.
+    pub fn accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Sends requests through your own [`Transport`] instead of the reqwest client, which
+    /// makes every other HTTP setting of this builder moot.
+    pub fn transport<T>(mut self, transport: T) -> Self
+    where
+        T: Transport + 'static,
+    {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// How requests that failed because of a transient problem are retried, see [`crate::retry`].
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn build(self) -> Result<Session, Error> {
+        let client = self
+            .inner
             // FIXME: there should be a better way of getting the bridge certs to work
             // without it blocking every single request we send its way
             // simply adding the root cert via pem file doesn't work here either
-            .danger_accept_invalid_certs(true)
+            .danger_accept_invalid_certs(self.accept_invalid_certs)
             .build()?;
 
-        Ok(Self { inner: client })
+        let verifies_certs = !self.accept_invalid_certs || self.transport.is_some();
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => Arc::new(client.clone()),
+        };
+
+        Ok(Session {
+            inner: client,
+            transport,
+            retry: self.retry,
+            verifies_certs,
+        })
+    }
+
+    fn map(self, f: impl FnOnce(ClientBuilder) -> ClientBuilder) -> Self {
+        Self {
+            inner: f(self.inner),
+            ..self
+        }
     }
 }
 
-impl std::ops::Deref for Session {
-    type Target = Client;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redacted(url: &str) -> String {
+        redacted_path(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn redact_app_key() {
+        assert_eq!(
+            redacted("https://192.168.1.2/api/s3cr3t-k3y/lights/1/state"),
+            "/api/<redacted>/lights/1/state"
+        );
+        assert_eq!(
+            redacted("https://192.168.1.2/api/s3cr3t-k3y"),
+            "/api/<redacted>"
+        );
+        assert_eq!(
+            redacted("https://192.168.1.2/api/s3cr3t-k3y/"),
+            "/api/<redacted>/"
+        );
+    }
+
+    #[test]
+    fn keep_unauthenticated_paths() {
+        assert_eq!(
+            redacted("https://192.168.1.2/api/0/config"),
+            "/api/0/config"
+        );
+        assert_eq!(redacted("https://192.168.1.2/api"), "/api");
+    }
+
+    #[test]
+    fn redact_remote_app_key() {
+        assert_eq!(
+            redacted("https://api.meethue.com/route/api/s3cr3t-k3y/groups/0/action"),
+            "/route/api/<redacted>/groups/0/action"
+        );
+        assert_eq!(
+            redacted("https://api.meethue.com/route/api/0/config"),
+            "/route/api/0/config"
+        );
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.inner
+    #[test]
+    fn keep_v2_paths() {
+        // the app key of the v2 api is sent as a header
+        assert_eq!(
+            redacted("https://192.168.1.2/clip/v2/resource/light/3f2a9c1e"),
+            "/clip/v2/resource/light/3f2a9c1e"
+        );
+        assert_eq!(
+            redacted("https://192.168.1.2/eventstream/clip/v2"),
+            "/eventstream/clip/v2"
+        );
     }
 }
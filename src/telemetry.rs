@@ -0,0 +1,101 @@
+//! Metrics of the requests sent to bridges, recorded through the [`metrics`](https://docs.rs/metrics)
+//! facade behind the `metrics` feature.
+//!
+//! Nothing is collected until you install a recorder, e.g. the one of
+//! [metrics-exporter-prometheus](https://docs.rs/metrics-exporter-prometheus). Every metric is
+//! labeled with the `bridge` it's about, requests which don't belong to an authenticated bridge
+//! yet (discovery, authentication, remote api tokens) are labeled `bridge="unknown"`.
+//!
+//! | Metric | Kind | Labels |
+//! |--------|------|--------|
+//! | [`REQUESTS`] | counter | `bridge`, `method`, `status` (`error` when there's no response) |
+//! | [`REQUEST_ERRORS`] | counter | `bridge`, `kind`: `status`, `connect`, `timeout`, `decode`, `transport`, `other` |
+//! | [`REQUEST_DURATION`] | histogram, seconds | `bridge`, `method` |
+//! | [`REQUESTS_IN_FLIGHT`] | gauge | `bridge` |
+//! | [`RETRIES`] | counter | `bridge`, `reason`: a status code, `connect`, `timeout` or a v1 error type (`api_901`) |
+//! | [`EVENT_STREAM_RECONNECTS`] | counter | `bridge` |
+//!
+//! Every attempt of a [retried](crate::retry) request counts as a request of its own. Bridges
+//! rate limiting you show up as `status="429"` and as retries with `reason="429"`.
+
+use crate::error::Error;
+use crate::retry::RetryReason;
+use crate::transport::{Method, Response};
+use std::time::Duration;
+
+pub const REQUESTS: &str = "lightrary_requests_total";
+pub const REQUEST_ERRORS: &str = "lightrary_request_errors_total";
+pub const REQUEST_DURATION: &str = "lightrary_request_duration_seconds";
+/// Requests being sent or waiting for their next attempt. There's no queue in front of the
+/// bridge, a steadily growing number means the bridge can't keep up.
+pub const REQUESTS_IN_FLIGHT: &str = "lightrary_requests_in_flight";
+pub const RETRIES: &str = "lightrary_retries_total";
+pub const EVENT_STREAM_RECONNECTS: &str = "lightrary_event_stream_reconnects_total";
+
+pub(crate) fn request(
+    bridge: &str,
+    method: &Method,
+    res: &Result<Response, Error>,
+    latency: Duration,
+) {
+    let status = match res {
+        Ok(res) => res.status().as_str().to_owned(),
+        Err(_) => "error".to_owned(),
+    };
+    metrics::counter!(
+        REQUESTS,
+        "bridge" => bridge.to_owned(),
+        "method" => method.to_string(),
+        "status" => status
+    )
+    .increment(1);
+    metrics::histogram!(
+        REQUEST_DURATION,
+        "bridge" => bridge.to_owned(),
+        "method" => method.to_string()
+    )
+    .record(latency.as_secs_f64());
+
+    let kind = match res {
+        Ok(res) if res.status().is_client_error() || res.status().is_server_error() => "status",
+        Ok(_) => return,
+        Err(Error::Reqwest(e)) if e.is_connect() => "connect",
+        Err(Error::Reqwest(e)) if e.is_timeout() => "timeout",
+        Err(Error::Reqwest(e)) if e.is_decode() => "decode",
+        Err(Error::Transport(_)) => "transport",
+        Err(_) => "other",
+    };
+    metrics::counter!(REQUEST_ERRORS, "bridge" => bridge.to_owned(), "kind" => kind).increment(1);
+}
+
+pub(crate) fn retry(bridge: &str, reason: RetryReason) {
+    let reason = match reason {
+        RetryReason::Status(status) => status.as_str().to_owned(),
+        RetryReason::Connect => "connect".to_owned(),
+        RetryReason::Timeout => "timeout".to_owned(),
+        RetryReason::Api(t) => format!("api_{}", t),
+    };
+    metrics::counter!(RETRIES, "bridge" => bridge.to_owned(), "reason" => reason).increment(1);
+}
+
+#[cfg(feature = "eventstream")]
+pub(crate) fn event_stream_reconnect(bridge: &str) {
+    metrics::counter!(EVENT_STREAM_RECONNECTS, "bridge" => bridge.to_owned()).increment(1);
+}
+
+/// Counts a request as in flight until dropped, which also covers requests that are cancelled.
+pub(crate) struct InFlight(metrics::Gauge);
+
+impl InFlight {
+    pub(crate) fn new(bridge: &str) -> Self {
+        let gauge = metrics::gauge!(REQUESTS_IN_FLIGHT, "bridge" => bridge.to_owned());
+        gauge.increment(1.0);
+        Self(gauge)
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.decrement(1.0);
+    }
+}
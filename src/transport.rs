@@ -0,0 +1,206 @@
+//! Pluggable HTTP backend of a [`Session`](crate::session::Session).
+//!
+//! Every request to a bridge ends up at the session's [`Transport`], which is the reqwest
+//! client by default. Implement it yourself to record and replay requests in tests, use
+//! another HTTP stack or wrap requests with your own instrumentation. Requests are plain
+//! [`http`](https://docs.rs/http) requests, responses are built with [`Response::new`].
+//!
+//! ```
+//! use async_trait::async_trait;
+//! use lightrary::discovery::{DiscoveryBroker, Manual};
+//! use lightrary::error::Error;
+//! use lightrary::session::Session;
+//! use lightrary::transport::{Request, Response, StatusCode, Transport};
+//!
+//! const CONFIG: &str = r#"{
+//!     "name": "Test bridge",
+//!     "datastoreversion": "131",
+//!     "swversion": "1953188020",
+//!     "apiversion": "1.56.0",
+//!     "mac": "00:17:88:6c:2a:3b",
+//!     "bridgeid": "001788FFFE6C2A3B",
+//!     "factorynew": false,
+//!     "modelid": "BSB002",
+//!     "starterkitid": ""
+//! }"#;
+//!
+//! /// Answers every request with the same canned bridge configuration.
+//! #[derive(Debug)]
+//! struct Replay;
+//!
+//! #[async_trait]
+//! impl Transport for Replay {
+//!     async fn execute(&self, request: Request) -> Result<Response, Error> {
+//!         println!("{} {}", request.method(), request.uri());
+//!         Ok(Response::new(StatusCode::OK, CONFIG))
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Error> {
+//! let session = Session::builder().transport(Replay).build()?;
+//! let manual = Manual::new()
+//!     .with_ip("192.168.1.2".parse()?)
+//!     .with_session(session.clone());
+//! let bridges = DiscoveryBroker::from(manual).discover().await?;
+//! for bridge in bridges {
+//!     let bridge = bridge.auth_with(&session).await.map_err(|(_, e)| e)?;
+//!     assert_eq!(bridge.config().name(), "Test bridge");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, Stream, StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+pub use http::{HeaderMap, HeaderValue, Method, StatusCode};
+
+/// Request handed to a [`Transport`], the body is empty for requests without one.
+pub type Request = http::Request<Vec<u8>>;
+
+/// Sends requests to bridges, see the [module docs](self).
+///
+/// Report failures of your own HTTP stack as [`Error::Transport`].
+#[async_trait]
+pub trait Transport: fmt::Debug + Send + Sync {
+    async fn execute(&self, request: Request) -> Result<Response, Error>;
+}
+
+#[async_trait]
+impl Transport for reqwest::Client {
+    async fn execute(&self, request: Request) -> Result<Response, Error> {
+        let res = reqwest::Client::execute(self, reqwest::Request::try_from(request)?).await?;
+        Ok(Response::streaming(
+            res.status(),
+            res.headers().clone(),
+            res.bytes_stream()
+                .map_ok(|bytes| bytes.to_vec())
+                .map_err(Error::from),
+        ))
+    }
+}
+
+/// Response of a [`Transport`], either with its whole body or with a body that's still
+/// being received.
+pub struct Response {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Body,
+}
+
+enum Body {
+    Full(Vec<u8>),
+    Stream(BoxStream<'static, Result<Vec<u8>, Error>>),
+}
+
+impl Response {
+    /// Builds a response without any connection behind it, e.g. to replay a recorded one.
+    pub fn new(status: StatusCode, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: Body::Full(body.into()),
+        }
+    }
+
+    /// Builds a response whose body arrives in chunks, which the event stream needs.
+    pub fn streaming<S>(status: StatusCode, headers: HeaderMap, body: S) -> Self
+    where
+        S: Stream<Item = Result<Vec<u8>, Error>> + Send + 'static,
+    {
+        Self {
+            status,
+            headers,
+            body: Body::Stream(body.boxed()),
+        }
+    }
+
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Turns `4xx` and `5xx` responses into [`Error::Status`].
+    pub fn error_for_status(self) -> Result<Self, Error> {
+        match self.status.is_client_error() || self.status.is_server_error() {
+            true => Err(Error::Status(self.status)),
+            false => Ok(self),
+        }
+    }
+
+    /// Waits for the whole body.
+    pub async fn bytes(self) -> Result<Vec<u8>, Error> {
+        match self.body {
+            Body::Full(body) => Ok(body),
+            Body::Stream(body) => {
+                body.try_fold(Vec::new(), |mut body, chunk| async move {
+                    body.extend(chunk);
+                    Ok(body)
+                })
+                .await
+            }
+        }
+    }
+
+    pub async fn text(self) -> Result<String, Error> {
+        Ok(String::from_utf8_lossy(&self.bytes().await?).into_owned())
+    }
+
+    pub async fn json<T>(self) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(serde_json::from_slice(&self.bytes().await?)?)
+    }
+
+    /// Receives the whole body, so it can be looked at before handing the response out.
+    pub(crate) async fn buffered(self) -> Result<Self, Error> {
+        let (status, headers) = (self.status, self.headers.clone());
+        Ok(Self::new(status, self.bytes().await?).with_headers(headers))
+    }
+
+    /// The body, when it has been received completely.
+    pub(crate) fn body(&self) -> Option<&[u8]> {
+        match &self.body {
+            Body::Full(body) => Some(body),
+            Body::Stream(_) => None,
+        }
+    }
+
+    /// The body in the chunks it's received in.
+    pub fn bytes_stream(self) -> BoxStream<'static, Result<Vec<u8>, Error>> {
+        match self.body {
+            Body::Full(body) => stream::once(async move { Ok(body) }).boxed(),
+            Body::Stream(body) => body,
+        }
+    }
+}
+
+impl From<http::Response<Vec<u8>>> for Response {
+    fn from(res: http::Response<Vec<u8>>) -> Self {
+        let (parts, body) = res.into_parts();
+        Self::new(parts.status, body).with_headers(parts.headers)
+    }
+}
+
+impl fmt::Debug for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Response")
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .field("streaming", &matches!(self.body, Body::Stream(_)))
+            .finish()
+    }
+}